@@ -1,16 +1,32 @@
-use crate::append_benchmark;
 use crate::git::{read_git_info, GitError};
-use crate::os::execute_and_measure;
+use crate::input::{Input, InputSummary};
+use crate::noise::{throttle_count, Conditions, Noise};
+use crate::os::{check_cgroup, check_counters, execute_and_measure};
+use crate::output::{output_id, sha256, OutputStorage, OutputSummary};
+use crate::provenance::{tool_versions, BinarySummary};
+use crate::shell::{Shell, ShellOverhead, ShellSummary};
+use crate::storage::Storage;
+use crate::sweep::{expand, substitute, unused_params, Combination, Param};
 use crate::system::System;
-use crate::utils::{is_key_value_pair, parse_key_value_pair};
+use crate::timeline::Timeline;
+use crate::utils::{is_key_value_pair, matches_pattern, parse_key_value_pair};
+use crate::validation::Expectations;
 use crate::Value;
 use crate::{value, GitInfo};
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use bytesize::ByteSize;
 use chrono::prelude::*;
 use colored::*;
 use itertools::Itertools;
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
 use std::time::Duration;
 
 #[derive(Serialize, Default, Deserialize, Debug, Clone)]
@@ -36,6 +52,30 @@ pub struct Benchmark {
     #[serde(flatten)]
     result: ExecutionResult,
 
+    #[serde(flatten)]
+    input: InputSummary,
+
+    #[serde(flatten)]
+    shell: ShellSummary,
+
+    #[serde(flatten)]
+    output: OutputSummary,
+
+    #[serde(flatten)]
+    binary: BinarySummary,
+
+    /// SHA-256 hash of the effective environment of the command
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    env_hash: Option<String>,
+
+    /// comma-separated conditions which made the run unreliable, e.g. "load,turbo"
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    noise_warnings: Option<String>,
+
+    /// whether the run met all expectations, only set if there are expectations
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    valid: Option<bool>,
+
     #[serde(flatten)]
     tags: HashMap<String, Value>,
 }
@@ -45,14 +85,22 @@ impl Benchmark {
         command: &[String],
         result: &ExecutionResult,
         git: &Option<GitInfo>,
+        system: System,
         tags: &HashMap<String, String>,
     ) -> Self {
         Self {
             command: command.join(" "),
             created_at: Utc::now(),
             git: git.clone(),
-            system: System::default(),
+            system,
             result: result.clone(),
+            input: InputSummary::default(),
+            shell: ShellSummary::default(),
+            output: OutputSummary::default(),
+            binary: BinarySummary::default(),
+            env_hash: None,
+            noise_warnings: None,
+            valid: None,
             tags: tags
                 .iter()
                 .map(|(key, value)| (key.clone(), value.into()))
@@ -72,8 +120,357 @@ pub struct ExecutionResult {
     #[serde(with = "value")]
     pub real_time: Duration,
 
+    /// the raw status as returned by `wait4` on Unix or the exit code on Windows,
+    /// use `exit_code` and `signal` instead
     #[serde(with = "value")]
     pub status_code: i64,
+
+    /// exit code of the command, not set if it was terminated by a signal
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i64>,
+
+    /// number of the signal which terminated the command
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub signal: Option<i64>,
+
+    /// name of the signal which terminated the command, e.g. "SIGSEGV"
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub signal_name: Option<String>,
+
+    /// whether the command dumped core when it was terminated by a signal
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub core_dumped: Option<bool>,
+
+    /// whether the command was killed after exceeding the timeout, only set with a timeout
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub timed_out: Option<bool>,
+
+    #[serde(flatten)]
+    pub counters: Counters,
+
+    #[serde(flatten)]
+    pub timeline: Timeline,
+
+    #[serde(flatten)]
+    pub io: IoAccounting,
+
+    #[serde(flatten)]
+    pub cgroup: CgroupUsage,
+}
+
+/// usage of all processes in the transient cgroup of the command, only collected on Linux
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CgroupUsage {
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cgroup_cpu_time: Option<Duration>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cgroup_user_time: Option<Duration>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cgroup_system_time: Option<Duration>,
+
+    /// only with the memory controller on Linux 5.19 or later
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cgroup_memory_peak: Option<ByteSize>,
+
+    /// bytes read from block devices, only with the io controller
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cgroup_read_bytes: Option<ByteSize>,
+
+    /// bytes written to block devices, only with the io controller
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cgroup_write_bytes: Option<ByteSize>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cgroup_read_ops: Option<i64>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cgroup_write_ops: Option<i64>,
+}
+
+/// I/O of the command and its descendants from `/proc/<pid>/io`, only collected on Linux
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IoAccounting {
+    /// bytes read with `read` and similar syscalls, including from the page cache
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub rchar: Option<ByteSize>,
+
+    /// bytes written with `write` and similar syscalls
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub wchar: Option<ByteSize>,
+
+    /// number of read syscalls
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub syscr: Option<i64>,
+
+    /// number of write syscalls
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub syscw: Option<i64>,
+
+    /// bytes actually fetched from the storage layer
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub read_bytes: Option<ByteSize>,
+
+    /// bytes actually sent to the storage layer
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub write_bytes: Option<ByteSize>,
+}
+
+/// performance counters of the command and its children in user space, only collected on Linux
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counters {
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<i64>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cycles: Option<i64>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cache_misses: Option<i64>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub branch_misses: Option<i64>,
+
+    /// CPU time in nanoseconds
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub task_clock: Option<i64>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub context_switches: Option<i64>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cpu_migrations: Option<i64>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub page_faults: Option<i64>,
+}
+
+impl Counters {
+    /// the collected counters in one line, `None` if no counters were collected
+    fn summary(&self) -> Option<String> {
+        let counters = [
+            ("instructions", self.instructions),
+            ("cycles", self.cycles),
+            ("cache misses", self.cache_misses),
+            ("branch misses", self.branch_misses),
+            ("context switches", self.context_switches),
+            ("CPU migrations", self.cpu_migrations),
+            ("page faults", self.page_faults),
+        ];
+
+        let summary = counters
+            .iter()
+            .filter_map(|(name, value)| value.map(|value| format!("{value} {name}")))
+            .join(" ");
+
+        (!summary.is_empty()).then_some(summary)
+    }
+}
+
+/// how the benchmarked command is executed
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionOptions {
+    /// working directory of the command, the current directory if not set
+    pub cwd: Option<PathBuf>,
+    /// environment variables added to the inherited environment
+    pub env: HashMap<String, String>,
+    /// the environment of benchie is not inherited, except for variables matching `env_keep`
+    pub env_clear: bool,
+    /// patterns like `LC_*` of variables which are inherited despite `env_clear`
+    pub env_keep: Vec<String>,
+    /// the command and all its children are killed when it runs longer
+    pub timeout: Option<Duration>,
+    /// resource limits applied to the command before it is executed
+    pub limits: ResourceLimits,
+    /// CPUs, priority and address space layout of the command
+    pub scheduling: Scheduling,
+    /// stderr is captured like stdout and scanned for `@benchie` lines
+    pub capture_stderr: bool,
+    /// output of the command is not printed
+    pub quiet: bool,
+    /// stdin of the command, inherited if not set
+    pub input: Option<Input>,
+    /// hardware and software performance counters are collected, only on Linux
+    pub counters: bool,
+    /// RSS and CPU usage of the command and its descendants are sampled at this interval,
+    /// only on Linux
+    pub sample_interval: Option<Duration>,
+    /// the command runs in a transient cgroup v2, to account for all processes it spawns,
+    /// only on Linux
+    pub cgroup: bool,
+}
+
+/// resource limits set with `setrlimit` for the benchmarked command, only supported on Unix
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// maximum size of the virtual memory
+    pub memory: Option<ByteSize>,
+    /// maximum CPU time, the command is killed when it is exceeded
+    pub cpu_time: Option<Duration>,
+    /// maximum number of open file descriptors
+    pub files: Option<u64>,
+}
+
+impl ExecutionOptions {
+    /// the effective environment of the command
+    pub fn environment(&self) -> HashMap<String, String> {
+        let mut vars: HashMap<_, _> = env::vars()
+            .filter(|(name, _)| {
+                !self.env_clear
+                    || self
+                        .env_keep
+                        .iter()
+                        .any(|pattern| matches_pattern(pattern, name))
+            })
+            .collect();
+
+        vars.extend(self.env.clone());
+
+        vars
+    }
+}
+
+/// scheduling of the benchmarked command to reduce variance, all but `nice` only on Linux
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scheduling {
+    /// the command only runs on these CPUs
+    pub cpus: Option<Vec<usize>>,
+    /// niceness of the command, negative values require privileges
+    pub nice: Option<i32>,
+    /// real-time scheduling policy with the lowest priority, requires privileges
+    pub policy: Option<SchedPolicy>,
+    /// disables address space layout randomization
+    pub no_aslr: bool,
+}
+
+impl Scheduling {
+    pub fn is_empty(&self) -> bool {
+        self.cpus.is_none() && self.nice.is_none() && self.policy.is_none() && !self.no_aslr
+    }
+
+    /// the scheduling as tags of the benchmark
+    pub(crate) fn tags(&self) -> HashMap<String, Value> {
+        let mut tags = HashMap::new();
+
+        if let Some(cpus) = &self.cpus {
+            tags.insert("cpus".to_string(), Value::String(cpus.iter().join(",")));
+        }
+        if let Some(nice) = self.nice {
+            tags.insert("nice".to_string(), Value::Integer(nice.into()));
+        }
+        if let Some(policy) = self.policy {
+            tags.insert("sched".to_string(), Value::String(policy.to_string()));
+        }
+        if self.no_aslr {
+            tags.insert("aslr".to_string(), Value::Bool(false));
+        }
+
+        tags
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchedPolicy {
+    Fifo,
+    RoundRobin,
+}
+
+impl SchedPolicy {
+    pub const VARIANTS: &'static [&'static str] = &["fifo", "rr"];
+}
+
+impl FromStr for SchedPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fifo" => Ok(SchedPolicy::Fifo),
+            "rr" => Ok(SchedPolicy::RoundRobin),
+            _ => Err(anyhow!(
+                "unknown scheduling policy \"{}\", expected one of: {}",
+                s,
+                SchedPolicy::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SchedPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            SchedPolicy::Fifo => "fifo",
+            SchedPolicy::RoundRobin => "rr",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_none() && self.cpu_time.is_none() && self.files.is_none()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchmarkOptions {
+    pub runs: usize,
+    pub execution: ExecutionOptions,
+    /// shell command executed once before all runs
+    pub setup: Option<String>,
+    /// shell command executed before every run, parameters are substituted as in the command
+    pub prepare: Option<String>,
+    /// shell command executed after every run, parameters are substituted as in the command
+    pub cleanup: Option<String>,
+    /// shell command executed once after all runs
+    pub teardown: Option<String>,
+    /// parameters substituted into the command, every combination is benchmarked `runs` times
+    pub params: Vec<Param>,
+    /// runs all combinations and runs in random order to reduce ordering bias
+    pub shuffle: bool,
+    /// how the output of every run is kept, not at all if not set
+    pub store_output: Option<OutputStorage>,
+    /// every run is checked against these and stored with `valid` set accordingly
+    pub expect: Expectations,
+    /// runs which do not meet the expectations are not stored
+    pub discard_invalid: bool,
+    /// the command is run by this shell, to support pipelines and redirections
+    pub shell: Option<Shell>,
+    /// the overhead of the shell is calibrated once and subtracted from the measured times
+    pub correct_shell_overhead: bool,
+    /// refuses to benchmark if the environment is noisy, e.g. because of a high load
+    pub strict: bool,
+    /// label of the machine, `BENCHIE_MACHINE` takes precedence
+    pub machine: Option<String>,
+    /// commands printing the versions of tools by name, recorded as `tool.<name>` tags
+    pub tools: HashMap<String, String>,
+    /// where benchmarks and outputs are stored
+    pub storage: Storage,
+}
+
+impl Default for BenchmarkOptions {
+    fn default() -> Self {
+        Self {
+            runs: 1,
+            execution: ExecutionOptions::default(),
+            setup: None,
+            prepare: None,
+            cleanup: None,
+            teardown: None,
+            params: vec![],
+            shuffle: false,
+            store_output: None,
+            expect: Expectations::default(),
+            discard_invalid: false,
+            shell: None,
+            correct_shell_overhead: false,
+            strict: false,
+            machine: None,
+            tools: HashMap::new(),
+            storage: Storage::default(),
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -110,7 +507,12 @@ pub fn parse_tags_from_stdout(output: &str) -> Result<HashMap<String, String>> {
     }
 }
 
-pub fn benchmark(command_and_flags: &[String], tags: &HashMap<String, String>) -> Result<()> {
+/// benchmarks the command as often as configured and stores every run as separate benchmark
+pub fn benchmark(
+    command_and_flags: &[String],
+    tags: &HashMap<String, String>,
+    options: &BenchmarkOptions,
+) -> Result<()> {
     let git_info = match read_git_info() {
         Ok(info) => {
             if info.is_dirty {
@@ -138,8 +540,351 @@ pub fn benchmark(command_and_flags: &[String], tags: &HashMap<String, String>) -
         }
     };
 
-    let (result, cmd_tags) =
-        execute_and_measure(command_and_flags).context("failed to execute command")?;
+    ensure!(
+        cfg!(unix) || !options.expect.checks_stdout(),
+        "checking stdout is not supported on Windows yet"
+    );
+    ensure!(
+        cfg!(unix) || options.shell.is_none(),
+        "running commands with a shell is not supported on Windows yet"
+    );
+    // joining arguments would lose their quoting, so the shell gets the command line as written
+    ensure!(
+        options.shell.is_none() || command_and_flags.len() == 1,
+        "a command run with a shell has to be passed as a single string, e.g. \"cat data | wc -l\", \
+         but got {} arguments",
+        command_and_flags.len()
+    );
+
+    // unavailable measurements are skipped with a warning instead of failing every run
+    let mut execution = options.execution.clone();
+    // the stored output of a run is only complete with stderr, which is still printed
+    if options.store_output.is_some() && cfg!(unix) {
+        execution.capture_stderr = true;
+    }
+    if let Err(error) = check_counters(&execution) {
+        println!(
+            "{}",
+            format!("warning: {error:#} => no performance counters will be collected").yellow()
+        );
+        execution.counters = false;
+    }
+    // held until all runs are done, so benchie does not leave and reenter its cgroup per run
+    let _cgroups = match check_cgroup(&execution) {
+        Ok(cgroups) => {
+            for controller in &cgroups.missing_controllers {
+                println!(
+                    "{}",
+                    format!(
+                        "warning: the {controller} controller can not be enabled for the cgroup => its usage is not stored"
+                    )
+                    .yellow()
+                );
+            }
+            Some(cgroups)
+        }
+        Err(error) => {
+            println!(
+                "{}",
+                format!("warning: {error:#} => the command is not run in a cgroup").yellow()
+            );
+            execution.cgroup = false;
+            None
+        }
+    };
+    let options = &BenchmarkOptions {
+        execution,
+        ..options.clone()
+    };
+
+    let noise = Conditions::read().noise();
+    for noise in &noise {
+        println!("{}", format!("warning: {}", noise.message).yellow());
+    }
+    ensure!(
+        noise.is_empty() || !options.strict,
+        "refusing to benchmark in a noisy environment, resolve the warnings above or omit --strict"
+    );
+
+    let environment = options.execution.environment();
+    let mut tags = tags.clone();
+    let versions = tool_versions(
+        &options.tools,
+        options.execution.cwd.as_deref(),
+        &environment,
+    );
+    for (tag, version) in versions {
+        match version {
+            Ok(version) => {
+                tags.insert(tag, version);
+            }
+            Err(error) => println!(
+                "{}",
+                format!("warning: {error:#} => \"{tag}\" will not be saved").yellow()
+            ),
+        }
+    }
+    let tags = &tags;
+
+    // tear down even if the setup or a run failed, to not leave state behind
+    let result = run_all(
+        command_and_flags,
+        tags,
+        &git_info,
+        &noise,
+        &environment,
+        options,
+    );
+    let teardown = match &options.teardown {
+        Some(teardown) => run_hook(teardown, &options.execution).context("teardown failed"),
+        None => Ok(()),
+    };
+
+    // the error of the runs is more relevant than a failing teardown it might have caused
+    result.and(teardown)
+}
+
+/// runs the setup and then all combinations of parameters as often as configured
+fn run_all(
+    command_and_flags: &[String],
+    tags: &HashMap<String, String>,
+    git_info: &Option<GitInfo>,
+    noise: &[Noise],
+    environment: &HashMap<String, String>,
+    options: &BenchmarkOptions,
+) -> Result<()> {
+    if let Some(setup) = &options.setup {
+        run_hook(setup, &options.execution).context("setup failed")?;
+    }
+
+    // after the setup, which might build the programs
+    let binaries = binary_summaries(command_and_flags, options, environment);
+
+    let overhead = match options.shell {
+        Some(shell) if options.correct_shell_overhead => {
+            let overhead = ShellOverhead::calibrate(shell, &options.execution)?;
+            println!(
+                "Overhead of {shell}: {:?} user {:?} system {:?} real",
+                overhead.user_time, overhead.system_time, overhead.real_time
+            );
+            Some(overhead)
+        }
+        _ => None,
+    };
+
+    for name in unused_params(command_and_flags, &options.params) {
+        println!(
+            "{}",
+            format!("warning: parameter \"{name}\" is not used as {{{name}}} in the command")
+                .yellow()
+        );
+    }
+
+    for (name, _) in &options.params {
+        if tags.contains_key(name) {
+            println!(
+                "{}",
+                format!("warning: parameter \"{name}\" overwrites the tag with the same key")
+                    .yellow()
+            );
+        }
+    }
+
+    let mut instances: Vec<_> = expand(&options.params)
+        .into_iter()
+        .flat_map(|combination| (0..options.runs).map(move |_| combination.clone()))
+        .collect();
+
+    if options.shuffle {
+        instances.shuffle(&mut rand::thread_rng());
+    }
+
+    let total = instances.len();
+    for (i, combination) in instances.iter().enumerate() {
+        if !combination.is_empty() {
+            let values = combination
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .join(", ");
+            println!("Run {}/{total} ({values})", i + 1);
+        } else if total > 1 {
+            println!("Run {}/{total}", i + 1);
+        }
+
+        if let Some(prepare) = &options.prepare {
+            run_hook(&substitute_hook(prepare, combination), &options.execution)
+                .context("prepare failed")?;
+        }
+
+        let command_and_flags = substitute(command_and_flags, combination);
+        let provenance = Provenance {
+            git_info,
+            binary: &binaries[&program(&command_and_flags, options.shell)],
+        };
+        let result = run_benchmark(
+            &command_and_flags,
+            tags,
+            combination,
+            &provenance,
+            options,
+            overhead.as_ref(),
+            noise,
+        );
+
+        // clean up even if the run failed, to not leave state behind for the next runs
+        if let Some(cleanup) = &options.cleanup {
+            run_hook(&substitute_hook(cleanup, combination), &options.execution)
+                .context("cleanup failed")?;
+        }
+
+        result?;
+    }
+
+    Ok(())
+}
+
+/// variables known to influence performance, recorded as `env.<name>` if they are set
+const RECORDED_ENV: &[&str] = &[
+    "RUST_LOG",
+    "RUST_BACKTRACE",
+    "MALLOC_ARENA_MAX",
+    "MALLOC_CONF",
+    "LD_PRELOAD",
+    "LD_LIBRARY_PATH",
+    "OMP_NUM_THREADS",
+    "RAYON_NUM_THREADS",
+    "GOMAXPROCS",
+    "GOGC",
+    "JAVA_TOOL_OPTIONS",
+    "PYTHONHASHSEED",
+];
+
+/// the SHA-256 hash of all `name=value` lines of the environment, sorted by name
+fn environment_hash(environment: &HashMap<String, String>) -> String {
+    let lines: String = environment
+        .iter()
+        .sorted()
+        .map(|(name, value)| format!("{name}={value}\n"))
+        .collect();
+
+    sha256(lines.as_bytes())
+}
+
+fn substitute_hook(hook: &str, combination: &Combination) -> String {
+    substitute(&[hook.to_string()], combination).remove(0)
+}
+
+/// runs a shell command outside of the measured region, with the same working directory and
+/// environment as the benchmarked command
+fn run_hook(command: &str, options: &ExecutionOptions) -> Result<()> {
+    let mut process = if cfg!(windows) {
+        let mut process = Command::new("cmd");
+        process.args(["/C", command]);
+        process
+    } else {
+        let mut process = Command::new("sh");
+        process.args(["-c", command]);
+        process
+    };
+
+    if let Some(cwd) = &options.cwd {
+        process.current_dir(cwd);
+    }
+
+    let status = process
+        .env_clear()
+        .envs(options.environment())
+        .status()
+        .with_context(|| format!("failed to execute \"{command}\""))?;
+
+    ensure!(status.success(), "\"{command}\" exited with {status}");
+
+    Ok(())
+}
+
+/// summaries of the programs of all combinations of parameters by program, as a parameter
+/// might choose the program, empty with a warning if a program can not be read
+fn binary_summaries(
+    command_and_flags: &[String],
+    options: &BenchmarkOptions,
+    environment: &HashMap<String, String>,
+) -> HashMap<String, BinarySummary> {
+    let cwd = options.execution.cwd.as_deref();
+
+    expand(&options.params)
+        .iter()
+        .map(|combination| program(&substitute(command_and_flags, combination), options.shell))
+        .unique()
+        .map(|program| {
+            let binary = BinarySummary::new(&program, cwd, environment).unwrap_or_else(|error| {
+                println!(
+                    "{}",
+                    format!("warning: {error:#} => no summary of \"{program}\" will be saved")
+                        .yellow()
+                );
+                BinarySummary::default()
+            });
+            (program, binary)
+        })
+        .collect()
+}
+
+/// the program run by the command, with a shell the first word of the command line which does
+/// not assign a variable
+fn program(command_and_flags: &[String], shell: Option<Shell>) -> String {
+    match shell {
+        Some(_) => command_and_flags[0]
+            .split_whitespace()
+            .find(|word| !is_assignment(word))
+            .unwrap_or_default()
+            .to_string(),
+        None => command_and_flags[0].clone(),
+    }
+}
+
+/// whether the word sets a variable for the command, like `LC_ALL=C`
+fn is_assignment(word: &str) -> bool {
+    match word.split_once('=') {
+        Some((name, _)) => {
+            !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+/// where the benchmarked code comes from, determined once before the runs
+struct Provenance<'a> {
+    git_info: &'a Option<GitInfo>,
+    binary: &'a BinarySummary,
+}
+
+fn run_benchmark(
+    command_and_flags: &[String],
+    tags: &HashMap<String, String>,
+    params: &Combination,
+    provenance: &Provenance,
+    options: &BenchmarkOptions,
+    overhead: Option<&ShellOverhead>,
+    noise: &[Noise],
+) -> Result<()> {
+    let executed = match options.shell {
+        Some(shell) => shell.wrap(&command_and_flags[0]),
+        None => command_and_flags.to_vec(),
+    };
+
+    // read before the command is spawned, so the load average does not include its own load
+    let system = System::default();
+    let throttled_before = throttle_count();
+    let (mut result, cmd_tags, output) =
+        execute_and_measure(&executed, &options.execution).context("failed to execute command")?;
+    let throttled = throttle_count().saturating_sub(throttled_before);
+
+    let mut shell = overhead
+        .map(|overhead| overhead.correct(&mut result))
+        .unwrap_or_default();
+    shell.shell = options.shell.map(|shell| shell.to_string());
 
     tags.iter().for_each(|(key, _)| {
         if cmd_tags.contains_key(key.as_str()) {
@@ -150,7 +895,15 @@ pub fn benchmark(command_and_flags: &[String], tags: &HashMap<String, String>) -
         }
     });
 
+    // parameters are stored as typed tags, unless the command outputs a tag with the same key
+    let typed_params: HashMap<_, _> = params
+        .iter()
+        .filter(|(name, _)| !cmd_tags.contains_key(name))
+        .map(|(name, value)| (name.clone(), Value::infer(value)))
+        .collect();
+
     let mut merged_tags = tags.clone();
+    merged_tags.retain(|key, _| !typed_params.contains_key(key));
     merged_tags.extend(cmd_tags);
 
     println!("Running \"{}\" took:", command_and_flags.join(" "));
@@ -158,19 +911,123 @@ pub fn benchmark(command_and_flags: &[String], tags: &HashMap<String, String>) -
         "{:?} user {:?} system {:?} real",
         result.user_time, result.system_time, result.real_time
     );
+    if let Some(summary) = result.counters.summary() {
+        println!("{summary}");
+    }
+    if let (Some(peak), Some(time_to_peak), Some(avg)) = (
+        result.timeline.peak_rss,
+        result.timeline.time_to_peak_rss,
+        result.timeline.avg_rss,
+    ) {
+        println!("{peak} peak RSS after {time_to_peak:?} {avg} average RSS");
+    }
 
-    if result.status_code != 0 {
+    if result.timed_out == Some(true) {
         println!(
             "{}",
             format!(
-                "warning: benchmarked program exited with status code {}",
-                result.status_code
+                "warning: benchmarked program was killed after exceeding the timeout of {:?}",
+                options.execution.timeout.unwrap_or_default()
             )
             .yellow()
         );
+    } else if let Some(signal) = result.signal {
+        let name = result
+            .signal_name
+            .as_ref()
+            .map_or_else(String::new, |name| format!(" ({name})"));
+        let core = if result.core_dumped == Some(true) {
+            ", core dumped"
+        } else {
+            ""
+        };
+
+        println!(
+            "{}",
+            format!("warning: benchmarked program was terminated by signal {signal}{name}{core}")
+                .yellow()
+        );
+    } else if let Some(exit_code) = result.exit_code.filter(|code| *code != 0) {
+        println!(
+            "{}",
+            format!("warning: benchmarked program exited with exit code {exit_code}").yellow()
+        );
     }
 
-    let benchmark = Benchmark::new(command_and_flags, &result, &git_info, &merged_tags);
+    let mut noise = noise.iter().map(|noise| noise.kind).collect_vec();
+    if throttled > 0 {
+        println!(
+            "{}",
+            format!("warning: the CPU was thermally throttled {throttled} times during the run")
+                .yellow()
+        );
+        noise.push("throttling");
+    }
 
-    append_benchmark(&benchmark).context("unable to save new benchmark")
+    let violations = options.expect.violations(&result, &output)?;
+    for violation in &violations {
+        println!("{}", format!("warning: invalid run, {violation}").yellow());
+    }
+
+    if !violations.is_empty() && options.discard_invalid {
+        println!("{}", "warning: invalid run is not stored".yellow());
+        return Ok(());
+    }
+
+    let mut benchmark = Benchmark::new(
+        command_and_flags,
+        &result,
+        provenance.git_info,
+        system,
+        &merged_tags,
+    );
+    benchmark.tags.extend(typed_params);
+    benchmark.valid = (!options.expect.is_empty()).then_some(violations.is_empty());
+    benchmark.input = InputSummary::new(&options.execution.input)?;
+    benchmark.shell = shell;
+    benchmark.system.identify(
+        env::var("BENCHIE_MACHINE")
+            .ok()
+            .or_else(|| options.machine.clone()),
+    );
+    benchmark.noise_warnings = (!noise.is_empty()).then(|| noise.join(","));
+    benchmark.tags.extend(options.execution.scheduling.tags());
+
+    let environment = options.execution.environment();
+    benchmark.env_hash = Some(environment_hash(&environment));
+    benchmark.binary = provenance.binary.clone();
+    benchmark.tags.extend(
+        environment
+            .iter()
+            .filter(|(name, _)| {
+                options.execution.env.contains_key(*name) || RECORDED_ENV.contains(&name.as_str())
+            })
+            .map(|(name, value)| (format!("env.{name}"), value.into())),
+    );
+
+    let mut output_dir = None;
+    benchmark.output = match options.store_output {
+        Some(OutputStorage::Full) => {
+            let id = output_id();
+            output_dir = Some(
+                options
+                    .storage
+                    .write_output(&id, &output)
+                    .context("unable to save output of command")?,
+            );
+            OutputSummary::new(&output, Some(id))
+        }
+        Some(OutputStorage::Hash) => OutputSummary::new(&output, None),
+        None => OutputSummary::default(),
+    };
+
+    let appended = options
+        .storage
+        .append_benchmark(&benchmark)
+        .context("unable to save new benchmark");
+    // no benchmark links to the output of a run that could not be saved
+    if let (Err(_), Some(output_dir)) = (&appended, output_dir) {
+        let _ = fs::remove_dir_all(output_dir);
+    }
+    appended
 }
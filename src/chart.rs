@@ -0,0 +1,404 @@
+use crate::value::DisplayOptions;
+use crate::Value;
+use anyhow::anyhow;
+use std::fmt;
+use std::str::FromStr;
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const BAR_PARTS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
+const MIN_PLOT_WIDTH: usize = 10;
+const LINE_CHART_HEIGHT: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chart {
+    Bar,
+    Line,
+    Box,
+}
+
+impl Chart {
+    pub const VARIANTS: &'static [&'static str] = &["bar", "line", "box"];
+}
+
+impl FromStr for Chart {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bar" => Ok(Chart::Bar),
+            "line" => Ok(Chart::Line),
+            "box" => Ok(Chart::Box),
+            _ => Err(anyhow!(
+                "unknown chart \"{}\", expected one of: {}",
+                s,
+                Chart::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Chart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Chart::Bar => "bar",
+            Chart::Line => "line",
+            Chart::Box => "box",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// the width of the terminal if stdout is a terminal, otherwise a sensible default
+pub fn terminal_width() -> usize {
+    terminal_size::terminal_size()
+        .map(|(terminal_size::Width(w), _)| w as usize)
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH)
+}
+
+/// renders the values as a one-line sparkline, scaled between the minimum and maximum value
+pub fn sparkline(values: &[f64]) -> String {
+    let (min, max) = bounds(values.iter().cloned());
+    let range = max - min;
+
+    values
+        .iter()
+        .map(|v| {
+            if range > 0.0 {
+                let index = ((v - min) / range * (SPARKS.len() - 1) as f64).round() as usize;
+                SPARKS[index.min(SPARKS.len() - 1)]
+            } else {
+                SPARKS[SPARKS.len() / 2]
+            }
+        })
+        .collect()
+}
+
+/// renders one horizontal bar per entry, scaled from zero to the biggest value
+pub fn bar_chart(entries: &[(String, Value)], width: usize, display: &DisplayOptions) -> String {
+    let label_width = max_len(entries.iter().map(|(label, _)| label));
+    let value_width = max_len(entries.iter().map(|(_, value)| display.format_value(value)));
+    let plot_width = plot_width(width, label_width + value_width + 3);
+
+    let (_, max) = bounds(entries.iter().filter_map(|(_, v)| v.as_f64()));
+
+    entries
+        .iter()
+        .map(|(label, value)| {
+            let v = value.as_f64().unwrap_or(0.0);
+            let bar = if max > 0.0 {
+                bar(v / max * plot_width as f64)
+            } else {
+                String::new()
+            };
+
+            format!(
+                "{:<label_width$} │{:<plot_width$} {}\n",
+                label,
+                bar,
+                display.format_value(value),
+                label_width = label_width,
+                plot_width = plot_width
+            )
+        })
+        .collect()
+}
+
+/// renders a line chart of the points in the given order, the first value of a point is
+/// used as label on the x axis
+pub fn line_chart(points: &[(String, Value)], width: usize, display: &DisplayOptions) -> String {
+    let numbers: Vec<_> = points.iter().filter_map(|(_, v)| v.as_f64()).collect();
+    let template = match points.first() {
+        Some((_, template)) if numbers.len() == points.len() => template,
+        _ => return String::new(),
+    };
+
+    let (min, max) = bounds(numbers.iter().cloned());
+    let format_axis = |v: f64| {
+        template
+            .with_f64(v)
+            .map_or(v.to_string(), |v| display.format_value(&v))
+    };
+    let axis_labels = [
+        format_axis(max),
+        format_axis((min + max) / 2.0),
+        format_axis(min),
+    ];
+    let axis_width = max_len(axis_labels.iter());
+    let plot_width = plot_width(width, axis_width + 2);
+
+    // spread the points over the plot and interpolate linearly between them
+    let columns: Vec<f64> = if numbers.len() == 1 {
+        vec![numbers[0]]
+    } else {
+        let columns = plot_width.min((numbers.len() - 1) * 8 + 1);
+        (0..columns)
+            .map(|c| {
+                let position = c as f64 / (columns - 1) as f64 * (numbers.len() - 1) as f64;
+                let left = position.floor() as usize;
+                let right = (left + 1).min(numbers.len() - 1);
+                let fraction = position - left as f64;
+                numbers[left] + (numbers[right] - numbers[left]) * fraction
+            })
+            .collect()
+    };
+
+    let to_row = |v: f64| {
+        if max > min {
+            ((max - v) / (max - min) * (LINE_CHART_HEIGHT - 1) as f64).round() as usize
+        } else {
+            LINE_CHART_HEIGHT / 2
+        }
+    };
+
+    let mut grid = vec![vec![' '; columns.len()]; LINE_CHART_HEIGHT];
+    for (c, v) in columns.iter().enumerate() {
+        grid[to_row(*v)][c] = '·';
+    }
+    for (i, v) in numbers.iter().enumerate() {
+        let c = if numbers.len() == 1 {
+            0
+        } else {
+            i * (columns.len() - 1) / (numbers.len() - 1)
+        };
+        grid[to_row(*v)][c] = '●';
+    }
+
+    let mut out = String::new();
+    for (r, line) in grid.iter().enumerate() {
+        let label = match r {
+            0 => &axis_labels[0],
+            r if r == LINE_CHART_HEIGHT / 2 => &axis_labels[1],
+            r if r == LINE_CHART_HEIGHT - 1 => &axis_labels[2],
+            _ => "",
+        };
+        out.push_str(&format!(
+            "{:>axis_width$} ┤{}\n",
+            label,
+            line.iter().collect::<String>().trim_end(),
+            axis_width = axis_width
+        ));
+    }
+
+    out.push_str(&format!(
+        "{:>axis_width$} └{}\n",
+        "",
+        "─".repeat(columns.len()),
+        axis_width = axis_width
+    ));
+
+    // label the x axis with the first and last point, if there is enough space for both
+    let first = &points[0].0;
+    let last = &points[points.len() - 1].0;
+    let x_labels =
+        if points.len() > 1 && first.chars().count() + last.chars().count() < columns.len() {
+            format!(
+                "{}{:>gap$}",
+                first,
+                last,
+                gap = columns.len() - first.chars().count()
+            )
+        } else {
+            first.chars().take(columns.len()).collect()
+        };
+    out.push_str(&format!(
+        "{:>axis_width$}  {}\n",
+        "",
+        x_labels,
+        axis_width = axis_width
+    ));
+
+    out
+}
+
+/// renders one box plot (minimum, lower quartile, median, upper quartile, maximum) per entry,
+/// all entries share the same scale
+pub fn box_plot(
+    entries: &[(String, Vec<Value>)],
+    width: usize,
+    display: &DisplayOptions,
+) -> String {
+    let summaries: Vec<_> = entries
+        .iter()
+        .filter(|(_, values)| !values.is_empty())
+        .filter_map(|(label, values)| {
+            let mut numbers = values
+                .iter()
+                .map(|v| v.as_f64())
+                .collect::<Option<Vec<_>>>()?;
+            numbers.sort_by(f64::total_cmp);
+            let median = display.format_value(&values[0].with_f64(quantile(&numbers, 0.5))?);
+
+            Some((label, numbers, median))
+        })
+        .collect();
+
+    let label_width = max_len(summaries.iter().map(|(label, _, _)| label));
+    let summary_width = max_len(
+        summaries
+            .iter()
+            .map(|(_, numbers, median)| format!("n={} median {}", numbers.len(), median)),
+    );
+    let plot_width = plot_width(width, label_width + summary_width + 4);
+
+    let (min, max) = bounds(summaries.iter().flat_map(|(_, n, _)| n.iter().cloned()));
+    let to_position = |v: f64| {
+        if max > min {
+            ((v - min) / (max - min) * (plot_width - 1) as f64).round() as usize
+        } else {
+            plot_width / 2
+        }
+    };
+
+    summaries
+        .iter()
+        .map(|(label, numbers, median)| {
+            let low = to_position(numbers[0]);
+            let q1 = to_position(quantile(numbers, 0.25));
+            let q2 = to_position(quantile(numbers, 0.5));
+            let q3 = to_position(quantile(numbers, 0.75));
+            let high = to_position(numbers[numbers.len() - 1]);
+
+            let mut line = vec![' '; plot_width];
+            line.iter_mut()
+                .take(high + 1)
+                .skip(low)
+                .for_each(|c| *c = '─');
+            line.iter_mut().take(q3 + 1).skip(q1).for_each(|c| *c = '█');
+            if high > low {
+                line[low] = '├';
+                line[high] = '┤';
+            }
+            line[q2] = '┃';
+
+            format!(
+                "{:<label_width$} │{}│ n={} median {}\n",
+                label,
+                line.iter().collect::<String>(),
+                numbers.len(),
+                median,
+                label_width = label_width
+            )
+        })
+        .collect()
+}
+
+/// linear interpolated quantile of sorted numbers
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let left = position.floor() as usize;
+    let right = position.ceil() as usize;
+
+    sorted[left] + (sorted[right] - sorted[left]) * (position - left as f64)
+}
+
+fn bar(length: f64) -> String {
+    let full = length.floor() as usize;
+    let eighths = ((length - full as f64) * 8.0).round() as usize;
+
+    let mut bar = BAR_PARTS[BAR_PARTS.len() - 1].to_string().repeat(full);
+    if eighths > 0 {
+        bar.push(BAR_PARTS[eighths - 1]);
+    }
+    bar
+}
+
+fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+        (min.min(v), max.max(v))
+    })
+}
+
+fn max_len<S: AsRef<str>>(strings: impl Iterator<Item = S>) -> usize {
+    strings
+        .map(|s| s.as_ref().chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+fn plot_width(width: usize, reserved: usize) -> usize {
+    width.saturating_sub(reserved).max(MIN_PLOT_WIDTH)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    fn ms(v: u64) -> Value {
+        Value::Duration(Duration::from_millis(v))
+    }
+
+    #[test]
+    fn sparkline_uses_lowest_and_highest_bar_for_extremes() {
+        let line = sparkline(&[1.0, 5.0, 3.0]);
+
+        assert_eq!(line.chars().count(), 3);
+        assert_eq!(line.chars().next(), Some('▁'));
+        assert_eq!(line.chars().nth(1), Some('█'));
+    }
+
+    #[test]
+    fn sparkline_of_constant_values_is_flat() {
+        let line = sparkline(&[2.0, 2.0, 2.0]);
+
+        assert!(line.chars().all(|c| c == line.chars().next().unwrap()));
+    }
+
+    #[test]
+    fn charts_do_not_exceed_the_given_width() {
+        let entries = vec![
+            ("bubblesort".to_string(), ms(3)),
+            ("mergesort".to_string(), ms(1)),
+        ];
+
+        for width in [40, 80, 120] {
+            let bars = bar_chart(&entries, width, &DisplayOptions::default());
+            let line = line_chart(&entries, width, &DisplayOptions::default());
+            let boxes = box_plot(
+                &[("bubblesort".to_string(), vec![ms(1), ms(2), ms(5)])],
+                width,
+                &DisplayOptions::default(),
+            );
+
+            for chart in [bars, line, boxes] {
+                assert!(
+                    chart.lines().all(|l| l.chars().count() <= width),
+                    "every line of the chart should fit into {} columns:\n{}",
+                    width,
+                    chart
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn biggest_value_gets_the_longest_bar() {
+        let chart = bar_chart(
+            &[("a".to_string(), ms(4)), ("b".to_string(), ms(2))],
+            39,
+            &DisplayOptions::default(),
+        );
+
+        let bars: Vec<_> = chart
+            .lines()
+            .map(|l| l.chars().filter(|c| *c == '█').count())
+            .collect();
+
+        assert!(bars[0] > bars[1]);
+        assert_eq!(bars[0], 2 * bars[1]);
+    }
+
+    #[test]
+    fn box_plot_shows_median_of_samples() {
+        let chart = box_plot(
+            &[("a".to_string(), vec![ms(1), ms(9), ms(2), ms(3), ms(4)])],
+            60,
+            &DisplayOptions::default(),
+        );
+
+        assert!(chart.contains("n=5 median 3ms"), "{}", chart);
+        assert!(chart.contains('┃'));
+    }
+}
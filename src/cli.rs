@@ -1,12 +1,24 @@
 use anyhow::{bail, Result};
-use benchie::{is_key_value_pair, parse_key_value_pair};
-use clap::{arg, crate_name, crate_version, Arg, Command, Values};
+use benchie::{
+    is_key_value_pair, parse_cpu_list, parse_duration, parse_key_value_pair, parse_param,
+    Aggregation, Chart, DisplayOptions, ExecutionOptions, Expectations, Input, OutputStorage,
+    Param, ResourceLimits, SchedPolicy, Scheduling, Shell, TimeUnit,
+};
+use bytesize::ByteSize;
+use clap::{arg, crate_name, crate_version, Arg, ArgGroup, Command, Values};
 use itertools::Itertools;
 use std::collections::HashMap;
 use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time::Duration;
 
 pub mod sub_commands {
     pub const SHOW: &str = "show";
+    pub const HISTORY: &str = "history";
+    pub const REPORT: &str = "report";
+    pub const SERVE: &str = "serve";
+    pub const TUI: &str = "tui";
+    pub const RUN: &str = "run";
 }
 
 #[derive(Debug, Clone)]
@@ -14,12 +26,52 @@ pub enum CliCommand {
     Benchmark {
         command: Vec<String>,
         tags: HashMap<String, String>,
+        runs: Option<usize>,
+        params: Vec<Param>,
+        shuffle: bool,
+        setup: Option<String>,
+        prepare: Option<String>,
+        cleanup: Option<String>,
+        execution: Box<ExecutionOptions>,
+        store_output: Option<OutputStorage>,
+        shell: Option<Shell>,
+        correct_shell_overhead: bool,
+        expect: Expectations,
+        discard_invalid: bool,
+        strict: bool,
     },
     Show {
         row: Option<String>,
         col: Option<String>,
         metric: Option<String>,
         filter: HashMap<String, String>,
+        chart: Option<Chart>,
+        display: DisplayOptions,
+        all_machines: bool,
+    },
+    History {
+        metric: String,
+        filter: HashMap<String, String>,
+        last: usize,
+        aggregation: Option<Aggregation>,
+        threshold: Option<f64>,
+        all_machines: bool,
+    },
+    Report {
+        out: PathBuf,
+        metrics: Vec<String>,
+        row: String,
+        col: Option<String>,
+        filter: HashMap<String, String>,
+        all_machines: bool,
+    },
+    Serve {
+        port: u16,
+    },
+    Tui,
+    Run {
+        suites: Vec<String>,
+        all: bool,
     },
 }
 
@@ -36,6 +88,164 @@ pub fn parse_arguments(args: &[OsString]) -> Result<CliCommand> {
                 .multiple_occurrences(true)
                 .validator(is_key_value_pair),
         )
+        .arg(
+            arg!(--runs <N> "How often the command is benchmarked")
+                .required(false)
+                .validator(|v| match v.parse::<usize>() {
+                    Ok(0) => Err("has to be at least 1".to_string()),
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(e.to_string()),
+                }),
+        )
+        .arg(
+            arg!(--param <PARAM> "Benchmarks every value of a parameter, e.g. n=10,100, substituted for {n} in the command")
+                .required(false)
+                .multiple_occurrences(true)
+                .validator(parse_param),
+        )
+        .arg(arg!(--shuffle "Runs all parameter combinations and runs in random order"))
+        .arg(
+            arg!(--setup <COMMAND> "Shell command executed once before all runs, not measured")
+                .required(false)
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            arg!(--prepare <COMMAND> "Shell command executed before every run, not measured")
+                .required(false)
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            arg!(--cleanup <COMMAND> "Shell command executed after every run, not measured")
+                .required(false)
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            arg!(--timeout <DURATION> "Kills the command and its children after e.g. 500ms, 30s or 5m")
+                .required(false)
+                .validator(parse_duration),
+        )
+        .arg(
+            arg!(--"limit-memory" <SIZE> "Limits the virtual memory of the command, e.g. 512MiB")
+                .required(false)
+                .validator(|v| v.parse::<ByteSize>()),
+        )
+        .arg(
+            arg!(--"limit-cpu-time" <DURATION> "Limits the CPU time of the command, e.g. 10s")
+                .required(false)
+                .validator(parse_duration),
+        )
+        .arg(
+            arg!(--"limit-files" <N> "Limits the number of open files of the command")
+                .required(false)
+                .validator(|v| v.parse::<u64>()),
+        )
+        .arg(
+            arg!(--cpu <LIST> "Pins the command to CPUs, e.g. 2 or 0,2-3 (Linux only)")
+                .required(false)
+                .validator(parse_cpu_list),
+        )
+        .arg(
+            arg!(--nice <N> "Niceness of the command, from -20 (highest priority) to 19")
+                .required(false)
+                .allow_hyphen_values(true)
+                .validator(|v| match v.parse::<i32>() {
+                    Ok(-20..=19) => Ok(()),
+                    Ok(_) => Err("has to be between -20 and 19".to_string()),
+                    Err(e) => Err(e.to_string()),
+                }),
+        )
+        .arg(
+            arg!(--sched <POLICY> "Runs the command with a real-time scheduling policy (Linux only)")
+                .required(false)
+                .possible_values(SchedPolicy::VARIANTS),
+        )
+        .arg(arg!(--"no-aslr" "Disables address space layout randomization for the command (Linux only)"))
+        .arg(arg!(--counters "Collects hardware and software performance counters of the command (Linux only)"))
+        .arg(arg!(--cgroup "Runs the command in a transient cgroup v2 to measure all processes it spawns (Linux only)"))
+        .arg(
+            arg!(--sample <INTERVAL> "Samples RSS and CPU usage of the command and its children at this interval of at least 1ms, e.g. 10ms (Linux only)")
+                .required(false)
+                .validator(|v| match parse_duration(v) {
+                    Ok(interval) if interval < Duration::from_millis(1) => {
+                        Err("has to be at least 1ms".to_string())
+                    }
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(e.to_string()),
+                }),
+        )
+        .arg(arg!(--"capture-stderr" "Captures stderr of the command and scans it for @benchie lines"))
+        .arg(arg!(--"quiet-command" "Does not print the output of the command"))
+        .arg(
+            arg!(--"store-output" <MODE> "Stores stdout and stderr of every run in .benchie/outputs (full) or only their hash and size (hash), implies --capture-stderr")
+                .required(false)
+                .possible_values(OutputStorage::VARIANTS),
+        )
+        .arg(
+            arg!(--input <FILE> "Feeds the file to stdin of the command, opened again for every run")
+                .required(false),
+        )
+        .arg(
+            arg!(--"input-null" "Uses /dev/null as stdin of the command")
+                .conflicts_with("input"),
+        )
+        .arg(
+            arg!(--cwd <DIR> "Working directory of the command and all hooks")
+                .required(false),
+        )
+        .arg(
+            arg!(--env <VAR> "Sets an environment variable of the command and all hooks, e.g. RUST_LOG=off")
+                .required(false)
+                .multiple_occurrences(true)
+                .validator(|v| match v.split_once('=') {
+                    Some((name, _)) if !name.is_empty() => Ok(()),
+                    _ => Err("has to be a <name>=<value> pair".to_string()),
+                }),
+        )
+        .arg(arg!(--"env-clear" "Does not pass the environment of benchie to the command"))
+        .arg(
+            arg!(--"env-keep" <PATTERN> "Keeps variables matching the pattern, e.g. PATH or LC_*, despite --env-clear")
+                .required(false)
+                .multiple_occurrences(true)
+                .requires("env-clear"),
+        )
+        .arg(
+            Arg::new("shell")
+                .long("shell")
+                .value_name("SHELL")
+                .help("Runs the command, passed as a single string, with a shell, to benchmark pipelines and redirections")
+                .takes_value(true)
+                .min_values(0)
+                .require_equals(true)
+                .default_missing_value("sh")
+                .possible_values(Shell::VARIANTS),
+        )
+        .arg(
+            arg!(--"correct-shell-overhead" "Subtracts the overhead of the shell, calibrated with an empty command, from the measured times")
+                .requires("shell"),
+        )
+        .arg(
+            arg!(--"expect-exit" <CODE> "Marks runs as invalid which do not exit with this exit code")
+                .required(false)
+                .allow_hyphen_values(true)
+                .validator(|v| v.parse::<i64>()),
+        )
+        .arg(
+            arg!(--"expect-stdout-file" <PATH> "Marks runs as invalid whose stdout differs from the file")
+                .required(false),
+        )
+        .arg(
+            arg!(--"expect-stdout-hash" <SHA256> "Marks runs as invalid whose stdout has a different SHA-256 hash")
+                .required(false)
+                .validator(|v| {
+                    if v.len() == 64 && v.chars().all(|c| c.is_ascii_hexdigit()) {
+                        Ok(())
+                    } else {
+                        Err("has to be a hex encoded SHA-256 hash".to_string())
+                    }
+                }),
+        )
+        .arg(arg!(--"discard-invalid" "Does not store runs which do not meet the expectations"))
+        .arg(arg!(--strict "Refuses to benchmark if the environment is noisy, e.g. because of a high load"))
         .arg(
             Arg::new("command")
                 .takes_value(true)
@@ -67,6 +277,29 @@ pub fn parse_arguments(args: &[OsString]) -> Result<CliCommand> {
                         .requires("row")
                         .requires("metric"),
                 )
+                .arg(
+                    arg!(--chart <CHART> "Renders the metric as chart instead of a table")
+                        .required(false)
+                        .possible_values(Chart::VARIANTS)
+                        .requires("row")
+                        .requires("metric"),
+                )
+                .arg(
+                    arg!(--aggregate <AGGREGATION> "Aggregates repeated values of a metric")
+                        .required(false)
+                        .possible_values(Aggregation::VARIANTS),
+                )
+                .arg(
+                    arg!(--unit <UNIT> "The unit to display durations in")
+                        .required(false)
+                        .possible_values(TimeUnit::VARIANTS),
+                )
+                .arg(
+                    arg!(--precision <DIGITS> "The number of decimal places to display")
+                        .required(false)
+                        .validator(|v| v.parse::<usize>()),
+                )
+                .arg(arg!(--"all-machines" "Compares benchmarks of different machines"))
                 .arg(
                     arg!(<METRIC> "The metric to display")
                         .required(false)
@@ -74,6 +307,104 @@ pub fn parse_arguments(args: &[OsString]) -> Result<CliCommand> {
                         .requires("row"),
                 ),
         )
+        .subcommand(
+            Command::new(sub_commands::HISTORY)
+                .about("Shows a metric along the first-parent commit history of the current branch")
+                .arg(
+                    arg!(--filter <PREDICATE> "The predicate to use to filter benchmarks")
+                        .required(false)
+                        .multiple_occurrences(true)
+                        .validator(is_key_value_pair),
+                )
+                .arg(
+                    arg!(--last <N> "The number of commits to walk back from HEAD")
+                        .short('n')
+                        .required(false)
+                        .default_value("20")
+                        .validator(|v| v.parse::<usize>()),
+                )
+                .arg(
+                    arg!(--aggregate <AGGREGATION> "The aggregation of repeated runs per commit")
+                        .required(false)
+                        .possible_values(Aggregation::VARIANTS),
+                )
+                .arg(
+                    arg!(--threshold <PERCENT> "Highlights changes between commits bigger than this")
+                        .required(false)
+                        .validator(|v| v.parse::<f64>()),
+                )
+                .arg(arg!(--"all-machines" "Compares benchmarks of different machines"))
+                .arg(
+                    arg!(<METRIC> "The metric to display")
+                        .required(true)
+                        .id("metric"),
+                ),
+        )
+        .subcommand(
+            Command::new(sub_commands::REPORT)
+                .about("Generates a self-contained HTML report of benchmarking results")
+                .arg(
+                    arg!(--out <FILE> "The file to write the report to")
+                        .short('o')
+                        .required(false)
+                        .default_value("report.html"),
+                )
+                .arg(
+                    arg!(--metric <METRIC> "The metrics to include in the report")
+                        .short('m')
+                        .required(false)
+                        .multiple_occurrences(true)
+                        .default_value("real_time"),
+                )
+                .arg(
+                    arg!(--row <ROW> "The key to compare the metrics by")
+                        .short('r')
+                        .required(false)
+                        .default_value("command"),
+                )
+                .arg(
+                    arg!(--col <COLUMN> "The column of the comparison tables")
+                        .short('c')
+                        .required(false),
+                )
+                .arg(
+                    arg!(--filter <PREDICATE> "The predicate to use to filter benchmarks")
+                        .required(false)
+                        .multiple_occurrences(true)
+                        .validator(is_key_value_pair),
+                )
+                .arg(arg!(--"all-machines" "Compares benchmarks of different machines")),
+        )
+        .subcommand(
+            Command::new(sub_commands::SERVE)
+                .about("Serves a local web dashboard to explore benchmarking results")
+                .arg(
+                    arg!(--port <PORT> "The local port to listen on")
+                        .short('p')
+                        .required(false)
+                        .default_value("8080")
+                        .validator(|v| v.parse::<u16>()),
+                ),
+        )
+        .subcommand(
+            Command::new(sub_commands::TUI)
+                .about("Explores benchmarking results interactively in the terminal"),
+        )
+        .subcommand(
+            Command::new(sub_commands::RUN)
+                .about("Runs the benchmark suites defined in benchie-suites.toml")
+                .arg(
+                    arg!([SUITE] "The suites to run")
+                        .id("suite")
+                        .multiple_values(true),
+                )
+                .arg(arg!(--all "Runs all suites").conflicts_with("suite"))
+                .group(
+                    ArgGroup::new("suites")
+                        .args(&["suite", "all"])
+                        .required(true),
+                ),
+        )
         .try_get_matches_from(args)?;
 
     Ok(match matches.subcommand() {
@@ -82,14 +413,155 @@ pub fn parse_arguments(args: &[OsString]) -> Result<CliCommand> {
             col: sub_commands.value_of("col").map(str::to_string),
             metric: sub_commands.value_of("metric").map(str::to_string),
             filter: parse_key_value_pairs(sub_commands.values_of("filter"))?,
+            chart: sub_commands.value_of("chart").map(str::parse).transpose()?,
+            display: DisplayOptions {
+                aggregation: sub_commands
+                    .value_of("aggregate")
+                    .map(str::parse)
+                    .transpose()?,
+                unit: sub_commands.value_of("unit").map(str::parse).transpose()?,
+                precision: sub_commands
+                    .value_of("precision")
+                    .map(str::parse)
+                    .transpose()?,
+            },
+            all_machines: sub_commands.is_present("all-machines"),
+        },
+        Some((sub_commands::HISTORY, sub_commands)) => CliCommand::History {
+            metric: sub_commands
+                .value_of("metric")
+                .expect("metric is required")
+                .to_string(),
+            filter: parse_key_value_pairs(sub_commands.values_of("filter"))?,
+            last: sub_commands.value_of_t("last")?,
+            aggregation: sub_commands
+                .value_of("aggregate")
+                .map(str::parse)
+                .transpose()?,
+            threshold: sub_commands
+                .value_of("threshold")
+                .map(str::parse)
+                .transpose()?,
+            all_machines: sub_commands.is_present("all-machines"),
+        },
+        Some((sub_commands::REPORT, sub_commands)) => CliCommand::Report {
+            out: PathBuf::from(sub_commands.value_of("out").expect("has default value")),
+            metrics: sub_commands
+                .values_of("metric")
+                .expect("has default value")
+                .map(str::to_string)
+                .collect(),
+            row: sub_commands
+                .value_of("row")
+                .expect("has default value")
+                .to_string(),
+            col: sub_commands.value_of("col").map(str::to_string),
+            filter: parse_key_value_pairs(sub_commands.values_of("filter"))?,
+            all_machines: sub_commands.is_present("all-machines"),
+        },
+        Some((sub_commands::SERVE, sub_commands)) => CliCommand::Serve {
+            port: sub_commands.value_of_t("port")?,
+        },
+        Some((sub_commands::TUI, _)) => CliCommand::Tui,
+        Some((sub_commands::RUN, sub_commands)) => CliCommand::Run {
+            suites: sub_commands
+                .values_of("suite")
+                .map(|suites| suites.map(str::to_string).collect())
+                .unwrap_or_default(),
+            all: sub_commands.is_present("all"),
         },
         m => {
             if let Some(command) = matches.values_of("command") {
                 let command: Vec<String> = command.into_iter().map(|s| s.to_owned()).collect();
 
                 let tags = parse_key_value_pairs(matches.values_of("tag"))?;
+                let runs = matches.value_of("runs").map(str::parse).transpose()?;
+                let params = parse_params(matches.values_of("param"))?;
+                let timeout = matches
+                    .value_of("timeout")
+                    .map(parse_duration)
+                    .transpose()?;
+                let limits = ResourceLimits {
+                    memory: matches
+                        .value_of("limit-memory")
+                        .map(str::parse)
+                        .transpose()
+                        .map_err(anyhow::Error::msg)?,
+                    cpu_time: matches
+                        .value_of("limit-cpu-time")
+                        .map(parse_duration)
+                        .transpose()?,
+                    files: matches
+                        .value_of("limit-files")
+                        .map(str::parse)
+                        .transpose()?,
+                };
+                let scheduling = Scheduling {
+                    cpus: matches.value_of("cpu").map(parse_cpu_list).transpose()?,
+                    nice: matches.value_of("nice").map(str::parse).transpose()?,
+                    policy: matches.value_of("sched").map(str::parse).transpose()?,
+                    no_aslr: matches.is_present("no-aslr"),
+                };
 
-                CliCommand::Benchmark { command, tags }
+                CliCommand::Benchmark {
+                    command,
+                    tags,
+                    runs,
+                    params,
+                    shuffle: matches.is_present("shuffle"),
+                    setup: matches.value_of("setup").map(str::to_string),
+                    prepare: matches.value_of("prepare").map(str::to_string),
+                    cleanup: matches.value_of("cleanup").map(str::to_string),
+                    execution: Box::new(ExecutionOptions {
+                        timeout,
+                        limits,
+                        scheduling,
+                        capture_stderr: matches.is_present("capture-stderr"),
+                        quiet: matches.is_present("quiet-command"),
+                        counters: matches.is_present("counters"),
+                        cgroup: matches.is_present("cgroup"),
+                        sample_interval: matches
+                            .value_of("sample")
+                            .map(parse_duration)
+                            .transpose()?,
+                        input: match (matches.value_of("input"), matches.is_present("input-null")) {
+                            (Some(path), _) => Some(Input::File(path.into())),
+                            (None, true) => Some(Input::Null),
+                            (None, false) => None,
+                        },
+                        cwd: matches.value_of("cwd").map(PathBuf::from),
+                        env: matches
+                            .values_of("env")
+                            .map(|values| {
+                                values
+                                    .filter_map(|v| v.split_once('='))
+                                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                                    .collect()
+                            })
+                            .unwrap_or_default(),
+                        env_clear: matches.is_present("env-clear"),
+                        env_keep: matches
+                            .values_of("env-keep")
+                            .map(|values| values.map(str::to_string).collect())
+                            .unwrap_or_default(),
+                    }),
+                    store_output: matches
+                        .value_of("store-output")
+                        .map(str::parse)
+                        .transpose()?,
+                    shell: matches.value_of("shell").map(str::parse).transpose()?,
+                    correct_shell_overhead: matches.is_present("correct-shell-overhead"),
+                    expect: Expectations {
+                        exit_code: matches
+                            .value_of("expect-exit")
+                            .map(str::parse)
+                            .transpose()?,
+                        stdout_file: matches.value_of("expect-stdout-file").map(PathBuf::from),
+                        stdout_hash: matches.value_of("expect-stdout-hash").map(str::to_string),
+                    },
+                    discard_invalid: matches.is_present("discard-invalid"),
+                    strict: matches.is_present("strict"),
+                }
             } else {
                 panic!(
                     "can not parse input arguments ({:?}) to subcommand {:?}",
@@ -100,6 +572,20 @@ pub fn parse_arguments(args: &[OsString]) -> Result<CliCommand> {
     })
 }
 
+fn parse_params(it: Option<Values>) -> Result<Vec<Param>> {
+    let params = it
+        .map(|values| values.map(parse_param).collect::<Result<Vec<_>>>())
+        .transpose()?
+        .unwrap_or_default();
+
+    let duplicates: Vec<_> = params.iter().map(|(name, _)| name).duplicates().collect();
+    if !duplicates.is_empty() {
+        bail!("parameters can only be passed once: {:?}", duplicates);
+    }
+
+    Ok(params)
+}
+
 fn parse_key_value_pairs(it: Option<Values>) -> Result<HashMap<String, String>> {
     let pairs: Vec<_> = it.map_or(vec![], |it| it.map(parse_key_value_pair).collect());
 
@@ -137,7 +623,7 @@ mod test {
     fn test_benchmark_command() {
         let result = parse_arguments(&[os("benchie"), os("time")]);
 
-        if let Ok(CliCommand::Benchmark { command, tags: _ }) = result {
+        if let Ok(CliCommand::Benchmark { command, .. }) = result {
             assert_eq!(command.len(), 1, "command should has length 1");
             assert_eq!(command[0], "time", "first part of command should be time");
         } else {
@@ -149,7 +635,7 @@ mod test {
     fn test_benchmark_with_hyphen_command_args() {
         let result = parse_arguments(&[os("benchie"), os("time"), os("--SHOW")]);
 
-        if let Ok(CliCommand::Benchmark { command, tags: _ }) = result {
+        if let Ok(CliCommand::Benchmark { command, .. }) = result {
             assert_eq!(command.len(), 2, "command should has length 2");
             assert_eq!(command[0], "time", "first part of command should be time");
             assert_eq!(
@@ -172,7 +658,14 @@ mod test {
                     row: None,
                     col: None,
                     metric: None,
-                    filter: _
+                    filter: _,
+                    chart: None,
+                    display: DisplayOptions {
+                        aggregation: None,
+                        unit: None,
+                        precision: None,
+                    },
+                    all_machines: false,
                 })
             ),
             "should succeed to parse show subcommand"
@@ -192,7 +685,7 @@ mod test {
                 row,
                 col: _,
                 metric,
-                filter: _,
+                ..
             }) => {
                 assert_eq!(row.unwrap(), "test_row");
                 assert_eq!(metric.unwrap(), "test_metric");
@@ -214,9 +707,9 @@ mod test {
     #[test]
     fn tag_arg_with_command_should_work() {
         match parse_arguments(&[os("benchie"), os("--tag"), os("key=value"), os("program")]) {
-            Ok(CliCommand::Benchmark { command, tags }) => {
+            Ok(CliCommand::Benchmark { command, tags, .. }) => {
                 assert_eq!(command.len(), 1);
-                assert_eq!(command.get(0).unwrap(), "program");
+                assert_eq!(command.first().unwrap(), "program");
                 assert_eq!(tags.len(), 1);
                 assert_eq!(tags.get("key").unwrap(), "value");
             }
@@ -236,10 +729,7 @@ mod test {
             os("test_metric"),
         ]) {
             Ok(CliCommand::Show {
-                row,
-                col,
-                metric,
-                filter: _,
+                row, col, metric, ..
             }) => {
                 assert_eq!(row.unwrap(), "test_row");
                 assert_eq!(col.unwrap(), "test_column");
@@ -259,7 +749,7 @@ mod test {
             os("bla=value"),
             os("program"),
         ]) {
-            Ok(CliCommand::Benchmark { command: _, tags }) => {
+            Ok(CliCommand::Benchmark { tags, .. }) => {
                 assert_eq!(tags.len(), 2);
             }
             _ => panic!("multiple tags should be allowed"),
@@ -301,4 +791,521 @@ mod test {
             _ => panic!("tag argument with command should work"),
         }
     }
+
+    #[test]
+    fn show_chart_requires_row_and_metric() {
+        let result = parse_arguments(&[os("benchie"), os("show"), os("--chart"), os("bar")]);
+
+        assert!(
+            result.is_err(),
+            "a chart without row and metric is not valid"
+        );
+
+        match parse_arguments(&[
+            os("benchie"),
+            os("show"),
+            os("--chart"),
+            os("box"),
+            os("--row"),
+            os("test_row"),
+            os("test_metric"),
+        ]) {
+            Ok(CliCommand::Show { chart, .. }) => assert_eq!(chart, Some(Chart::Box)),
+            _ => panic!("show with chart, row and metric should work"),
+        }
+    }
+
+    #[test]
+    fn report_subcommand_accepts_multiple_metrics() {
+        match parse_arguments(&[
+            os("benchie"),
+            os("report"),
+            os("--out"),
+            os("out.html"),
+            os("--metric"),
+            os("real_time"),
+            os("--metric"),
+            os("user_time"),
+        ]) {
+            Ok(CliCommand::Report {
+                out,
+                metrics,
+                row,
+                col,
+                ..
+            }) => {
+                assert_eq!(out, PathBuf::from("out.html"));
+                assert_eq!(metrics, vec!["real_time", "user_time"]);
+                assert_eq!(row, "command");
+                assert_eq!(col, None);
+            }
+            _ => panic!("report with multiple metrics should work"),
+        }
+    }
+
+    #[test]
+    fn serve_subcommand_validates_port() {
+        assert!(matches!(
+            parse_arguments(&[os("benchie"), os("serve")]),
+            Ok(CliCommand::Serve { port: 8080 })
+        ));
+        assert!(parse_arguments(&[os("benchie"), os("serve"), os("--port"), os("99999")]).is_err());
+    }
+
+    #[test]
+    fn runs_have_to_be_positive() {
+        match parse_arguments(&[os("benchie"), os("--runs"), os("3"), os("program")]) {
+            Ok(CliCommand::Benchmark { command, runs, .. }) => {
+                assert_eq!(command, vec!["program"]);
+                assert_eq!(runs, Some(3));
+            }
+            _ => panic!("runs with a command should work"),
+        }
+
+        assert!(parse_arguments(&[os("benchie"), os("--runs"), os("0"), os("program")]).is_err());
+    }
+
+    #[test]
+    fn params_are_parsed_in_order() {
+        match parse_arguments(&[
+            os("benchie"),
+            os("--param"),
+            os("n=10,100"),
+            os("--param"),
+            os("algo=bubble,merge"),
+            os("--shuffle"),
+            os("--"),
+            os("./sort"),
+            os("--algo"),
+            os("{algo}"),
+        ]) {
+            Ok(CliCommand::Benchmark {
+                command,
+                params,
+                shuffle,
+                ..
+            }) => {
+                assert_eq!(command, vec!["./sort", "--algo", "{algo}"]);
+                assert_eq!(params[0].0, "n");
+                assert_eq!(params[1].1, vec!["bubble", "merge"]);
+                assert!(shuffle);
+            }
+            _ => panic!("params with a command should work"),
+        }
+
+        assert!(parse_arguments(&[
+            os("benchie"),
+            os("--param"),
+            os("n=1"),
+            os("--param"),
+            os("n=2"),
+            os("program")
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn hooks_are_passed_as_single_shell_commands() {
+        match parse_arguments(&[
+            os("benchie"),
+            os("--setup"),
+            os("make build"),
+            os("--prepare"),
+            os("rm -rf data"),
+            os("--cleanup"),
+            os("sync"),
+            os("program"),
+        ]) {
+            Ok(CliCommand::Benchmark {
+                command,
+                setup,
+                prepare,
+                cleanup,
+                ..
+            }) => {
+                assert_eq!(command, vec!["program"]);
+                assert_eq!(setup.as_deref(), Some("make build"));
+                assert_eq!(prepare.as_deref(), Some("rm -rf data"));
+                assert_eq!(cleanup.as_deref(), Some("sync"));
+            }
+            _ => panic!("hooks with a command should work"),
+        }
+    }
+
+    #[test]
+    fn timeout_and_limits_are_parsed() {
+        match parse_arguments(&[
+            os("benchie"),
+            os("--timeout"),
+            os("1.5s"),
+            os("--limit-memory"),
+            os("1MiB"),
+            os("--limit-cpu-time"),
+            os("2m"),
+            os("--limit-files"),
+            os("64"),
+            os("program"),
+        ]) {
+            Ok(CliCommand::Benchmark { execution, .. }) => {
+                assert_eq!(execution.timeout, Some(Duration::from_millis(1500)));
+                assert_eq!(execution.limits.memory, Some(ByteSize::mib(1)));
+                assert_eq!(execution.limits.cpu_time, Some(Duration::from_secs(120)));
+                assert_eq!(execution.limits.files, Some(64));
+            }
+            _ => panic!("timeout and limits with a command should work"),
+        }
+
+        assert!(
+            parse_arguments(&[os("benchie"), os("--timeout"), os("soon"), os("program")]).is_err()
+        );
+    }
+
+    #[test]
+    fn output_options_are_parsed() {
+        match parse_arguments(&[
+            os("benchie"),
+            os("--capture-stderr"),
+            os("--quiet-command"),
+            os("--store-output"),
+            os("hash"),
+            os("program"),
+        ]) {
+            Ok(CliCommand::Benchmark {
+                execution,
+                store_output,
+                ..
+            }) => {
+                assert!(execution.capture_stderr);
+                assert!(execution.quiet);
+                assert_eq!(store_output, Some(OutputStorage::Hash));
+            }
+            _ => panic!("output options with a command should work"),
+        }
+
+        assert!(parse_arguments(&[
+            os("benchie"),
+            os("--store-output"),
+            os("everything"),
+            os("program")
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn expectations_are_parsed() {
+        match parse_arguments(&[
+            os("benchie"),
+            os("--expect-exit"),
+            os("-1"),
+            os("--expect-stdout-file"),
+            os("expected.txt"),
+            os("--discard-invalid"),
+            os("program"),
+        ]) {
+            Ok(CliCommand::Benchmark {
+                command,
+                expect,
+                discard_invalid,
+                ..
+            }) => {
+                assert_eq!(command, vec!["program"]);
+                assert_eq!(expect.exit_code, Some(-1));
+                assert_eq!(expect.stdout_file, Some(PathBuf::from("expected.txt")));
+                assert!(discard_invalid);
+            }
+            _ => panic!("expectations with a command should work"),
+        }
+
+        assert!(parse_arguments(&[
+            os("benchie"),
+            os("--expect-stdout-hash"),
+            os("abc"),
+            os("program")
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn input_is_a_file_or_null() {
+        match parse_arguments(&[os("benchie"), os("--input"), os("corpus.txt"), os("gzip")]) {
+            Ok(CliCommand::Benchmark { execution, .. }) => {
+                assert_eq!(
+                    execution.input,
+                    Some(Input::File(PathBuf::from("corpus.txt")))
+                );
+            }
+            _ => panic!("input with a command should work"),
+        }
+
+        match parse_arguments(&[os("benchie"), os("--input-null"), os("gzip")]) {
+            Ok(CliCommand::Benchmark { execution, .. }) => {
+                assert_eq!(execution.input, Some(Input::Null))
+            }
+            _ => panic!("null input with a command should work"),
+        }
+
+        assert!(parse_arguments(&[
+            os("benchie"),
+            os("--input"),
+            os("corpus.txt"),
+            os("--input-null"),
+            os("gzip")
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn environment_and_working_directory_are_parsed() {
+        match parse_arguments(&[
+            os("benchie"),
+            os("--cwd"),
+            os("examples"),
+            os("--env"),
+            os("RUST_LOG=off"),
+            os("--env"),
+            os("URL=http://host/?a=b"),
+            os("--env-clear"),
+            os("--env-keep"),
+            os("PATH"),
+            os("--env-keep"),
+            os("LC_*"),
+            os("program"),
+        ]) {
+            Ok(CliCommand::Benchmark {
+                command, execution, ..
+            }) => {
+                assert_eq!(command, vec!["program"]);
+                assert_eq!(execution.cwd, Some(PathBuf::from("examples")));
+                assert_eq!(execution.env["RUST_LOG"], "off");
+                assert_eq!(execution.env["URL"], "http://host/?a=b");
+                assert!(execution.env_clear);
+                assert_eq!(execution.env_keep, vec!["PATH", "LC_*"]);
+            }
+            _ => panic!("environment options with a command should work"),
+        }
+
+        assert!(
+            parse_arguments(&[os("benchie"), os("--env-keep"), os("PATH"), os("program")]).is_err()
+        );
+    }
+
+    #[test]
+    fn shell_defaults_to_sh() {
+        match parse_arguments(&[
+            os("benchie"),
+            os("--shell"),
+            os("--correct-shell-overhead"),
+            os("cat data | wc -l"),
+        ]) {
+            Ok(CliCommand::Benchmark {
+                command,
+                shell,
+                correct_shell_overhead,
+                ..
+            }) => {
+                assert_eq!(command, vec!["cat data | wc -l"]);
+                assert_eq!(shell, Some(Shell::Sh));
+                assert!(correct_shell_overhead);
+            }
+            _ => panic!("shell with a command should work"),
+        }
+
+        match parse_arguments(&[os("benchie"), os("--shell=bash"), os("ls > out")]) {
+            Ok(CliCommand::Benchmark { shell, .. }) => assert_eq!(shell, Some(Shell::Bash)),
+            _ => panic!("shell with a value should work"),
+        }
+
+        assert!(
+            parse_arguments(&[os("benchie"), os("--correct-shell-overhead"), os("program")])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn scheduling_options_are_parsed() {
+        match parse_arguments(&[
+            os("benchie"),
+            os("--cpu"),
+            os("0,2-3"),
+            os("--nice"),
+            os("-5"),
+            os("--sched"),
+            os("fifo"),
+            os("--no-aslr"),
+            os("program"),
+        ]) {
+            Ok(CliCommand::Benchmark { execution, .. }) => {
+                assert_eq!(
+                    execution.scheduling,
+                    Scheduling {
+                        cpus: Some(vec![0, 2, 3]),
+                        nice: Some(-5),
+                        policy: Some(SchedPolicy::Fifo),
+                        no_aslr: true,
+                    }
+                );
+            }
+            _ => panic!("scheduling options with a command should work"),
+        }
+
+        assert!(parse_arguments(&[os("benchie"), os("--nice"), os("20"), os("program")]).is_err());
+    }
+
+    #[test]
+    fn counters_are_enabled() {
+        match parse_arguments(&[os("benchie"), os("--counters"), os("program")]) {
+            Ok(CliCommand::Benchmark { execution, .. }) => assert!(execution.counters),
+            _ => panic!("counters with a command should work"),
+        }
+
+        match parse_arguments(&[os("benchie"), os("program")]) {
+            Ok(CliCommand::Benchmark { execution, .. }) => assert!(!execution.counters),
+            _ => panic!("a command without options should work"),
+        }
+    }
+
+    #[test]
+    fn strict_is_parsed() {
+        match parse_arguments(&[os("benchie"), os("--strict"), os("program")]) {
+            Ok(CliCommand::Benchmark { strict, .. }) => assert!(strict),
+            _ => panic!("strict with a command should work"),
+        }
+    }
+
+    #[test]
+    fn cgroup_is_enabled() {
+        match parse_arguments(&[os("benchie"), os("--cgroup"), os("program")]) {
+            Ok(CliCommand::Benchmark { execution, .. }) => assert!(execution.cgroup),
+            _ => panic!("cgroup with a command should work"),
+        }
+    }
+
+    #[test]
+    fn sample_interval_is_parsed() {
+        match parse_arguments(&[os("benchie"), os("--sample"), os("10ms"), os("program")]) {
+            Ok(CliCommand::Benchmark { execution, .. }) => {
+                assert_eq!(execution.sample_interval, Some(Duration::from_millis(10)));
+            }
+            _ => panic!("sampling with a command should work"),
+        }
+
+        assert!(
+            parse_arguments(&[os("benchie"), os("--sample"), os("often"), os("program")]).is_err()
+        );
+        assert!(
+            parse_arguments(&[os("benchie"), os("--sample"), os("0"), os("program")]).is_err(),
+            "sampling without a pause should be rejected"
+        );
+        assert!(
+            parse_arguments(&[os("benchie"), os("--sample"), os("999us"), os("program")]).is_err()
+        );
+    }
+
+    #[test]
+    fn show_accepts_display_options() {
+        match parse_arguments(&[
+            os("benchie"),
+            os("show"),
+            os("--row"),
+            os("command"),
+            os("--aggregate"),
+            os("median"),
+            os("--unit"),
+            os("ms"),
+            os("--precision"),
+            os("2"),
+            os("real_time"),
+        ]) {
+            Ok(CliCommand::Show { display, .. }) => assert_eq!(
+                display,
+                DisplayOptions {
+                    aggregation: Some(Aggregation::Median),
+                    unit: Some(TimeUnit::Ms),
+                    precision: Some(2),
+                }
+            ),
+            _ => panic!("show with display options should work"),
+        }
+    }
+
+    #[test]
+    fn run_subcommand_requires_suites_or_all() {
+        match parse_arguments(&[os("benchie"), os("run"), os("sorting"), os("startup")]) {
+            Ok(CliCommand::Run { suites, all }) => {
+                assert_eq!(suites, vec!["sorting", "startup"]);
+                assert!(!all);
+            }
+            _ => panic!("run with suites should work"),
+        }
+
+        assert!(matches!(
+            parse_arguments(&[os("benchie"), os("run"), os("--all")]),
+            Ok(CliCommand::Run { all: true, .. })
+        ));
+        assert!(parse_arguments(&[os("benchie"), os("run")]).is_err());
+        assert!(parse_arguments(&[os("benchie"), os("run"), os("--all"), os("sorting")]).is_err());
+    }
+
+    #[test]
+    fn tui_subcommand_works() {
+        assert!(matches!(
+            parse_arguments(&[os("benchie"), os("tui")]),
+            Ok(CliCommand::Tui)
+        ));
+    }
+
+    #[test]
+    fn history_subcommand_requires_a_metric() {
+        let result = parse_arguments(&[os("benchie"), os("history")]);
+
+        assert!(result.is_err(), "history without a metric is not valid");
+    }
+
+    #[test]
+    fn history_subcommand_has_defaults() {
+        match parse_arguments(&[
+            os("benchie"),
+            os("history"),
+            os("--filter"),
+            os("command=ls"),
+            os("real_time"),
+        ]) {
+            Ok(CliCommand::History {
+                metric,
+                filter,
+                last,
+                aggregation,
+                threshold,
+                all_machines,
+            }) => {
+                assert_eq!(metric, "real_time");
+                assert_eq!(filter.get("command"), Some(&"ls".to_string()));
+                assert_eq!(last, 20);
+                assert_eq!(aggregation, None, "defaults to the configured aggregation");
+                assert_eq!(threshold, None);
+                assert!(!all_machines);
+            }
+            _ => panic!("history with a metric and filter should work"),
+        }
+    }
+
+    #[test]
+    fn machines_can_be_mixed_explicitly() {
+        match parse_arguments(&[os("benchie"), os("report"), os("--all-machines")]) {
+            Ok(CliCommand::Report { all_machines, .. }) => assert!(all_machines),
+            _ => panic!("report with --all-machines should work"),
+        }
+
+        match parse_arguments(&[
+            os("benchie"),
+            os("show"),
+            os("--all-machines"),
+            os("--row"),
+            os("command"),
+            os("real_time"),
+        ]) {
+            Ok(CliCommand::Show { all_machines, .. }) => assert!(all_machines),
+            _ => panic!("show with --all-machines should work"),
+        }
+    }
 }
@@ -0,0 +1,188 @@
+use crate::storage::project_dir_path;
+use crate::value::DisplayOptions;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CONFIG_FILE: &str = "benchie.toml";
+
+/// project configuration, read from `benchie.toml` next to the Git root.
+/// Every option can be overridden by the corresponding CLI flag.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub benchmark: BenchmarkConfig,
+    pub storage: StorageConfig,
+    pub display: DisplayOptions,
+    pub regression: RegressionConfig,
+}
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BenchmarkConfig {
+    /// how often a command is benchmarked per invocation
+    pub runs: Option<usize>,
+    /// tags added to every benchmark, tags given on the command line take precedence
+    pub tags: HashMap<String, String>,
+    /// label of this machine, part of the machine ID, overridden by `BENCHIE_MACHINE`
+    pub machine: Option<String>,
+    /// shell commands printing the versions of tools by name, e.g. `rustc = "rustc -V"`
+    pub tools: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// directory of the data file, relative to the Git root
+    pub path: Option<PathBuf>,
+    pub format: StorageFormat,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StorageFormat {
+    #[default]
+    #[serde(rename = "json")]
+    Json,
+    #[serde(rename = "pretty-json")]
+    PrettyJson,
+}
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegressionConfig {
+    /// relative change in percent between two commits, which is reported as regression
+    pub threshold: Option<f64>,
+    /// thresholds of single metrics, overriding the default threshold
+    pub metrics: HashMap<String, f64>,
+}
+
+impl RegressionConfig {
+    pub fn threshold(&self, metric: &str) -> Option<f64> {
+        self.metrics.get(metric).cloned().or(self.threshold)
+    }
+}
+
+impl Config {
+    /// loads the configuration of the current project, or the default configuration if there
+    /// is no configuration file
+    pub fn load() -> Result<Config> {
+        Self::load_from(&project_dir_path())
+    }
+
+    pub(crate) fn load_from(dir: &Path) -> Result<Config> {
+        let path = dir.join(CONFIG_FILE);
+
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let raw = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+
+        let config: Config =
+            toml::from_str(&raw).with_context(|| format!("failed to parse {}", path.display()))?;
+
+        config
+            .validate()
+            .with_context(|| format!("invalid {}", path.display()))?;
+
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.benchmark.runs == Some(0) {
+            bail!("benchmark.runs has to be at least 1");
+        }
+
+        let mut thresholds = self
+            .regression
+            .threshold
+            .iter()
+            .chain(self.regression.metrics.values());
+
+        if thresholds.any(|t| *t < 0.0) {
+            bail!("regression thresholds can not be negative");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::value::{Aggregation, TimeUnit};
+    use tempfile::tempdir;
+
+    #[test]
+    fn missing_config_file_results_in_default_config() {
+        let dir = tempdir().unwrap();
+
+        assert_eq!(Config::load_from(dir.path()).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn all_sections_are_parsed() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            r#"
+[benchmark]
+runs = 5
+tags = { machine = "ci" }
+machine = "lab-1"
+tools = { rustc = "rustc -V" }
+
+[storage]
+path = "benchmarks"
+format = "pretty-json"
+
+[display]
+aggregation = "median"
+unit = "ms"
+precision = 2
+
+[regression]
+threshold = 5.0
+metrics = { max_rss = 10.0 }
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load_from(dir.path()).unwrap();
+
+        assert_eq!(config.benchmark.runs, Some(5));
+        assert_eq!(
+            config.benchmark.tags.get("machine"),
+            Some(&"ci".to_string())
+        );
+        assert_eq!(config.benchmark.machine.as_deref(), Some("lab-1"));
+        assert_eq!(
+            config.benchmark.tools.get("rustc"),
+            Some(&"rustc -V".to_string())
+        );
+        assert_eq!(config.storage.path, Some(PathBuf::from("benchmarks")));
+        assert_eq!(config.storage.format, StorageFormat::PrettyJson);
+        assert_eq!(config.display.aggregation, Some(Aggregation::Median));
+        assert_eq!(config.display.unit, Some(TimeUnit::Ms));
+        assert_eq!(config.display.precision, Some(2));
+        assert_eq!(config.regression.threshold("real_time"), Some(5.0));
+        assert_eq!(config.regression.threshold("max_rss"), Some(10.0));
+    }
+
+    #[test]
+    fn invalid_config_is_reported() {
+        let dir = tempdir().unwrap();
+
+        fs::write(dir.path().join(CONFIG_FILE), "[benchmark]\nruns = 0\n").unwrap();
+        assert!(Config::load_from(dir.path()).is_err());
+
+        fs::write(dir.path().join(CONFIG_FILE), "[display]\nunits = \"ms\"\n").unwrap();
+        assert!(
+            Config::load_from(dir.path()).is_err(),
+            "unknown keys should not be ignored silently"
+        );
+    }
+}
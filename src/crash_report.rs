@@ -199,9 +199,7 @@ mod test {
     }
 
     fn big_panic_info() -> String {
-        (1..1000)
-            .into_iter()
-            .fold(String::new(), |res, _| res + " Hello World!")
+        (1..1000).fold(String::new(), |res, _| res + " Hello World!")
     }
 
     fn small_panic_info() -> String {
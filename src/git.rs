@@ -1,6 +1,6 @@
 use crate::value;
 use anyhow::{anyhow, ensure, Context, Result};
-use git2::{BranchType, Commit, Repository, StatusOptions, Statuses};
+use git2::{BranchType, Commit, Oid, Repository, StatusOptions, Statuses};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use thiserror::Error;
@@ -51,6 +51,32 @@ pub fn read_git_info() -> Result<GitInfo, GitError> {
     })
 }
 
+/// reads up to `limit` commits (id and first line of the message) of the first-parent history
+/// of the current HEAD, in order from newest to oldest.
+pub fn read_first_parent_history(limit: usize) -> Result<Vec<(String, String)>, GitError> {
+    let repo = discover_repository()?;
+
+    // ensure HEAD points to a commit, otherwise there is no history to walk
+    let _ = read_head_commit(&repo)?;
+
+    let mut revwalk = repo
+        .revwalk()
+        .context("failed to create revision walker for Git repository")?;
+    revwalk
+        .push_head()
+        .and_then(|_| revwalk.simplify_first_parent())
+        .context("failed to start revision walk at HEAD")?;
+
+    revwalk
+        .take(limit)
+        .map(|oid| {
+            oid.and_then(|oid: Oid| repo.find_commit(oid))
+                .map(commit_to_details)
+                .map_err(|error| GitError::Unknown(anyhow!(error)))
+        })
+        .collect()
+}
+
 fn discover_repository() -> Result<Repository, GitError> {
     Repository::discover(".").map_err(|error| {
         if error.code() == git2::ErrorCode::NotFound {
@@ -90,7 +116,7 @@ fn read_current_branch(repo: &Repository) -> Result<Option<String>, GitError> {
     Ok(branch_name)
 }
 
-fn read_head_commit(repo: &Repository) -> Result<Commit, GitError> {
+fn read_head_commit(repo: &Repository) -> Result<Commit<'_>, GitError> {
     repo.head()
         .and_then(|h| h.peel_to_commit())
         .map_err(|_| GitError::NoCommit)
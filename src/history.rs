@@ -0,0 +1,268 @@
+use crate::chart::sparkline;
+use crate::git::read_first_parent_history;
+use crate::show::{apply_filter, check_machines};
+use crate::value::{Aggregation, DisplayOptions};
+use crate::{BenchmarkRaw, Storage, Value, Values};
+use anyhow::{Context, Result};
+use cli_table::{format::Justify, Cell, Style, Table, TableStruct};
+use colored::*;
+use std::collections::HashMap;
+
+const COMMIT_ID_LENGTH: usize = 8;
+
+pub(crate) struct HistoryEntry {
+    pub(crate) commit_id: String,
+    pub(crate) commit_message: String,
+    pub(crate) values: Values,
+}
+
+impl HistoryEntry {
+    fn display_value(&self, aggregation: Aggregation, display: &DisplayOptions) -> String {
+        DisplayOptions {
+            aggregation: Some(aggregation),
+            ..*display
+        }
+        .format_values(&self.values)
+    }
+}
+
+/// prints the metric per commit, changes to the previous commit which are bigger than the
+/// threshold (in percent) are highlighted, values per commit are aggregated by the mean if the
+/// display options have no aggregation
+pub fn show_history(
+    storage: &Storage,
+    metric: &str,
+    filter: &HashMap<String, String>,
+    last: usize,
+    display: &DisplayOptions,
+    threshold: Option<f64>,
+    all_machines: bool,
+) -> Result<()> {
+    let commits = read_first_parent_history(last)
+        .context("failed to read commit history of Git repository")?;
+    let benchmarks = storage.load_all_benchmarks()?;
+    let aggregation = display.aggregation.unwrap_or(Aggregation::Mean);
+    check_machines(&benchmarks, filter, &[], all_machines)?;
+
+    let entries = compute_history(&benchmarks, &commits, metric, filter);
+
+    println!("Showing history along the first-parent commits of HEAD:");
+    println!(
+        "metric: {}, aggregation: {}, commits: {}\n",
+        metric,
+        aggregation,
+        commits.len()
+    );
+
+    if entries.is_empty() {
+        println!("Result is empty");
+        return Ok(());
+    }
+
+    let aggregated: Vec<_> = entries
+        .iter()
+        .map(|e| e.values.aggregate(aggregation).and_then(|v| v.as_f64()))
+        .collect();
+    let changes = relative_changes(&aggregated);
+
+    println!(
+        "{}",
+        build_history_table(metric, aggregation, display, &entries, &changes, threshold)
+            .display()?
+    );
+
+    if let Some(threshold) = threshold {
+        let exceeding = changes
+            .iter()
+            .flatten()
+            .filter(|c| c.abs() > threshold)
+            .count();
+
+        if exceeding > 0 {
+            println!(
+                "{}",
+                format!(
+                    "warning: {exceeding} change(s) exceed the regression threshold of {threshold}%"
+                )
+                .yellow()
+            );
+        }
+    }
+
+    let trend: Vec<_> = aggregated.into_iter().flatten().collect();
+
+    if trend.len() > 1 {
+        println!("trend (oldest → newest): {}", sparkline(&trend));
+    }
+
+    Ok(())
+}
+
+/// groups all benchmarks by commit and returns the entries ordered from oldest to newest commit.
+/// `commits` is expected to be ordered from newest to oldest, as returned by a revision walk.
+pub(crate) fn compute_history(
+    benchmarks: &[BenchmarkRaw],
+    commits: &[(String, String)],
+    metric: &str,
+    filter: &HashMap<String, String>,
+) -> Vec<HistoryEntry> {
+    let mut values_per_commit = HashMap::<String, Values>::new();
+
+    for benchmark in benchmarks.iter().filter(|b| apply_filter(b, filter)) {
+        if let (Some(Value::String(commit_id)), Some(metric_value)) =
+            (benchmark.data.get("commit_id"), benchmark.data.get(metric))
+        {
+            values_per_commit
+                .entry(commit_id.clone())
+                .or_insert_with(|| Values(vec![]))
+                .push(metric_value.clone());
+        }
+    }
+
+    commits
+        .iter()
+        .rev()
+        .filter_map(|(commit_id, commit_message)| {
+            values_per_commit
+                .remove(commit_id)
+                .map(|values| HistoryEntry {
+                    commit_id: commit_id.clone(),
+                    commit_message: commit_message.clone(),
+                    values,
+                })
+        })
+        .collect()
+}
+
+/// change in percent of every value relative to the previous value
+pub(crate) fn relative_changes(values: &[Option<f64>]) -> Vec<Option<f64>> {
+    let mut changes = vec![None];
+
+    for window in values.windows(2) {
+        changes.push(match (window[0], window[1]) {
+            (Some(previous), Some(current)) if previous != 0.0 => {
+                Some((current - previous) / previous * 100.0)
+            }
+            _ => None,
+        });
+    }
+
+    changes.truncate(values.len());
+    changes
+}
+
+fn format_change(change: Option<f64>, threshold: Option<f64>) -> String {
+    match (change, threshold) {
+        (Some(change), Some(threshold)) if change.abs() > threshold => {
+            let arrow = if change > 0.0 { '▲' } else { '▼' };
+            format!("{arrow} {change:+.1}%")
+        }
+        (Some(change), _) => format!("{change:+.1}%"),
+        (None, _) => String::new(),
+    }
+}
+
+fn build_history_table(
+    metric: &str,
+    aggregation: Aggregation,
+    display: &DisplayOptions,
+    entries: &[HistoryEntry],
+    changes: &[Option<f64>],
+    threshold: Option<f64>,
+) -> TableStruct {
+    entries
+        .iter()
+        .zip(changes)
+        .map(|(entry, change)| {
+            vec![
+                entry
+                    .commit_id
+                    .chars()
+                    .take(COMMIT_ID_LENGTH)
+                    .collect::<String>()
+                    .cell(),
+                entry.commit_message.clone().cell(),
+                entry.values.0.len().cell().justify(Justify::Right),
+                entry
+                    .display_value(aggregation, display)
+                    .cell()
+                    .justify(Justify::Right),
+                format_change(*change, threshold)
+                    .cell()
+                    .justify(Justify::Right),
+            ]
+        })
+        .table()
+        .title(vec![
+            "commit".cell().bold(true),
+            "message".cell().bold(true),
+            "runs".cell().bold(true),
+            metric.to_string().cell().bold(true),
+            "change".cell().bold(true),
+        ])
+        .bold(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    fn benchmark(commit_id: &str, real_time: u64) -> BenchmarkRaw {
+        let mut b = BenchmarkRaw::default();
+        b.data.insert(
+            "commit_id".to_string(),
+            Value::String(commit_id.to_string()),
+        );
+        b.data.insert(
+            "real_time".to_string(),
+            Value::Duration(Duration::from_millis(real_time)),
+        );
+        b
+    }
+
+    fn commits() -> Vec<(String, String)> {
+        vec![
+            ("c3".to_string(), "third".to_string()),
+            ("c2".to_string(), "second".to_string()),
+            ("c1".to_string(), "first".to_string()),
+        ]
+    }
+
+    #[test]
+    fn history_is_ordered_from_oldest_to_newest_commit() {
+        let benchmarks = vec![benchmark("c3", 1), benchmark("c1", 2)];
+
+        let entries = compute_history(&benchmarks, &commits(), "real_time", &HashMap::new());
+
+        let ids: Vec<_> = entries.iter().map(|e| e.commit_id.as_str()).collect();
+        assert_eq!(ids, vec!["c1", "c3"]);
+    }
+
+    #[test]
+    fn repeated_runs_are_aggregated_per_commit() {
+        let benchmarks = vec![
+            benchmark("c2", 1),
+            benchmark("c2", 3),
+            benchmark("unknown", 100),
+        ];
+
+        let entries = compute_history(&benchmarks, &commits(), "real_time", &HashMap::new());
+
+        assert_eq!(entries.len(), 1, "commits outside the history are ignored");
+        assert_eq!(entries[0].values.0.len(), 2);
+        assert_eq!(
+            entries[0].values.aggregate(Aggregation::Mean),
+            Some(Value::Duration(Duration::from_millis(2)))
+        );
+    }
+
+    #[test]
+    fn changes_are_relative_to_the_previous_commit() {
+        let changes = relative_changes(&[Some(2.0), Some(3.0), None, Some(1.0), Some(0.5)]);
+
+        assert_eq!(changes, vec![None, Some(50.0), None, None, Some(-50.0)]);
+        assert_eq!(format_change(changes[1], Some(10.0)), "▲ +50.0%");
+        assert_eq!(format_change(changes[4], Some(60.0)), "-50.0%");
+    }
+}
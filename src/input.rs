@@ -0,0 +1,81 @@
+use crate::value;
+use anyhow::{Context, Result};
+use bytesize::ByteSize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io;
+use std::path::PathBuf;
+
+/// where the benchmarked command reads its stdin from, benchie's stdin if not set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    /// the file is opened again for every run
+    File(PathBuf),
+    Null,
+}
+
+/// path, size and hash of the input of a run, stored with the benchmark
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputSummary {
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub input_path: Option<String>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub input_size: Option<ByteSize>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub input_hash: Option<String>,
+}
+
+impl InputSummary {
+    pub(crate) fn new(input: &Option<Input>) -> Result<Self> {
+        let path = match input {
+            Some(Input::File(path)) => path,
+            Some(Input::Null) => {
+                return Ok(Self {
+                    input_path: Some("/dev/null".to_string()),
+                    ..Default::default()
+                })
+            }
+            None => return Ok(Self::default()),
+        };
+
+        let mut file =
+            File::open(path).with_context(|| format!("failed to open input {}", path.display()))?;
+
+        let mut hasher = Sha256::new();
+        let size = io::copy(&mut file, &mut hasher)
+            .with_context(|| format!("failed to read input {}", path.display()))?;
+
+        Ok(Self {
+            input_path: Some(path.display().to_string()),
+            input_size: Some(ByteSize::b(size)),
+            input_hash: Some(format!("{:x}", hasher.finalize())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn input_file_is_summarized() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("corpus");
+        fs::write(&path, "hello\n").unwrap();
+
+        let summary = InputSummary::new(&Some(Input::File(path))).unwrap();
+
+        assert_eq!(summary.input_size, Some(ByteSize::b(6)));
+        assert_eq!(
+            summary.input_hash.as_deref(),
+            Some("5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03")
+        );
+        assert_eq!(InputSummary::new(&None).unwrap(), InputSummary::default());
+        assert!(InputSummary::new(&Some(Input::File(dir.path().join("missing")))).is_err());
+    }
+}
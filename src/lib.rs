@@ -1,20 +1,53 @@
 extern crate core;
 
 mod benchmark;
+mod chart;
+mod config;
 mod crash_report;
 mod git;
+mod history;
+mod input;
+mod noise;
 mod os;
+mod output;
+mod provenance;
+mod report;
+mod serve;
+mod shell;
 mod show;
 mod storage;
+mod suite;
+mod sweep;
 mod system;
+mod timeline;
+mod tui;
 mod utils;
+mod validation;
 mod value;
 
-pub use benchmark::{benchmark, Benchmark, BenchmarkRaw, ExecutionResult};
+pub use benchmark::{
+    benchmark, Benchmark, BenchmarkOptions, BenchmarkRaw, CgroupUsage, Counters, ExecutionOptions,
+    ExecutionResult, ResourceLimits, SchedPolicy, Scheduling,
+};
+pub use chart::Chart;
+pub use config::Config;
 pub use crash_report::initialize_crash_reporter;
-pub use git::{read_git_info, GitError, GitInfo};
+pub use git::{read_first_parent_history, read_git_info, GitError, GitInfo};
+pub use history::show_history;
+pub use input::{Input, InputSummary};
 pub use os::execute_and_measure;
-pub use show::{show, show_1d_table, show_2d_table};
-pub use storage::{append_benchmark, load_all_benchmarks, Data};
-pub use utils::{is_key_value_pair, parse_key_value_pair};
-pub use value::{Value, Values};
+pub use output::{CommandOutput, OutputStorage, OutputSummary};
+pub use provenance::BinarySummary;
+pub use report::report;
+pub use serve::serve;
+pub use shell::{Shell, ShellSummary};
+pub use show::{show, show_1d_table, show_2d_table, show_chart};
+pub use storage::{append_benchmark, load_all_benchmarks, Data, Storage};
+pub use suite::run_suites;
+pub use sweep::{parse_param, Param};
+pub use system::System;
+pub use timeline::Timeline;
+pub use tui::tui;
+pub use utils::{is_key_value_pair, parse_cpu_list, parse_duration, parse_key_value_pair};
+pub use validation::Expectations;
+pub use value::{Aggregation, DisplayOptions, TimeUnit, Value, Values};
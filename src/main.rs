@@ -1,7 +1,11 @@
 use crate::cli::CliCommand;
 use anyhow::Result;
-use benchie::{benchmark, initialize_crash_reporter};
-use benchie::{show, show_1d_table, show_2d_table};
+use benchie::{
+    benchmark, initialize_crash_reporter, run_suites, BenchmarkOptions, Config, DisplayOptions,
+    Storage,
+};
+use benchie::{report, serve, show, show_1d_table, show_2d_table, show_chart, show_history, tui};
+use colored::*;
 use std::env;
 
 mod cli;
@@ -11,17 +15,146 @@ fn main() -> Result<()> {
 
     let raw_args: Vec<_> = env::args_os().collect();
 
-    match cli::parse_arguments(&raw_args)? {
-        CliCommand::Benchmark { command, tags } => benchmark(&command, &tags),
+    let command = cli::parse_arguments(&raw_args)?;
+    let config = match Config::load() {
+        Ok(config) => config,
+        // reading the benchmarks does not depend on the config enough to refuse to show them
+        Err(error)
+            if !matches!(
+                command,
+                CliCommand::Benchmark { .. } | CliCommand::Run { .. }
+            ) =>
+        {
+            println!(
+                "{}",
+                format!("warning: ignoring the config => {:#}", error).yellow()
+            );
+            Config::default()
+        }
+        Err(error) => return Err(error),
+    };
+    // resolved once, so every run of this invocation is stored in the same place
+    let storage = Storage::new(&config.storage);
+
+    match command {
+        CliCommand::Benchmark {
+            command,
+            tags,
+            runs,
+            params,
+            shuffle,
+            setup,
+            prepare,
+            cleanup,
+            execution,
+            store_output,
+            shell,
+            correct_shell_overhead,
+            expect,
+            discard_invalid,
+            strict,
+        } => {
+            let mut merged_tags = config.benchmark.tags;
+            merged_tags.extend(tags);
+
+            let options = BenchmarkOptions {
+                runs: runs.or(config.benchmark.runs).unwrap_or(1),
+                params,
+                shuffle,
+                setup,
+                prepare,
+                cleanup,
+                execution: *execution,
+                store_output,
+                shell,
+                correct_shell_overhead,
+                expect,
+                discard_invalid,
+                strict,
+                machine: config.benchmark.machine,
+                tools: config.benchmark.tools,
+                storage,
+                ..Default::default()
+            };
+
+            benchmark(&command, &merged_tags, &options)
+        }
         CliCommand::Show {
             row,
             col,
             metric,
             filter,
-        } => match (row, col, metric) {
-            (Some(row), Some(col), Some(metric)) => show_2d_table(&row, &col, &metric, &filter),
-            (Some(row), _, Some(metric)) => show_1d_table(&row, &metric, &filter),
-            _ => show(&filter),
+            chart,
+            display,
+            all_machines,
+        } => match (row, col, metric, chart) {
+            (Some(row), col, Some(metric), Some(chart)) => show_chart(
+                &storage,
+                chart,
+                &row,
+                col.as_deref(),
+                &metric,
+                &filter,
+                &display.or(config.display),
+                all_machines,
+            ),
+            (Some(row), Some(col), Some(metric), None) => show_2d_table(
+                &storage,
+                &row,
+                &col,
+                &metric,
+                &filter,
+                &display.or(config.display),
+                all_machines,
+            ),
+            (Some(row), _, Some(metric), None) => show_1d_table(
+                &storage,
+                &row,
+                &metric,
+                &filter,
+                &display.or(config.display),
+                all_machines,
+            ),
+            _ => show(&storage, &filter),
         },
+        CliCommand::History {
+            metric,
+            filter,
+            last,
+            aggregation,
+            threshold,
+            all_machines,
+        } => show_history(
+            &storage,
+            &metric,
+            &filter,
+            last,
+            &DisplayOptions {
+                aggregation: aggregation.or(config.display.aggregation),
+                ..config.display
+            },
+            threshold.or_else(|| config.regression.threshold(&metric)),
+            all_machines,
+        ),
+        CliCommand::Report {
+            out,
+            metrics,
+            row,
+            col,
+            filter,
+            all_machines,
+        } => report(
+            &storage,
+            &out,
+            &metrics,
+            &row,
+            col.as_deref(),
+            &filter,
+            &config.display,
+            all_machines,
+        ),
+        CliCommand::Serve { port } => serve(&storage, port),
+        CliCommand::Tui => tui(&storage),
+        CliCommand::Run { suites, all } => run_suites(&suites, all, &config, &storage),
     }
 }
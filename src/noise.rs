@@ -0,0 +1,197 @@
+use bytesize::ByteSize;
+use std::fs;
+use sysinfo::SystemExt;
+
+/// load average per logical core above which other processes compete with the benchmark
+const MAX_LOAD_PER_CORE: f64 = 0.5;
+
+/// share of available memory below which the benchmark might swap or evict caches
+const MIN_AVAILABLE_MEMORY: f64 = 0.1;
+
+/// a condition which makes benchmark results unreliable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Noise {
+    /// short name stored with the benchmark, e.g. "governor"
+    pub kind: &'static str,
+    pub message: String,
+}
+
+/// the state of the machine relevant for noise, read before benchmarking
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Conditions {
+    pub load_average: Option<f64>,
+    pub cores: usize,
+    pub available_memory: ByteSize,
+    pub total_memory: ByteSize,
+    /// CPU frequency governors of all CPUs, empty if unknown
+    pub governors: Vec<String>,
+    pub turbo: Option<bool>,
+    pub on_battery: bool,
+}
+
+impl Conditions {
+    pub(crate) fn read() -> Self {
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+        system.refresh_cpu();
+
+        Self {
+            load_average: cfg!(unix).then(|| system.load_average().one),
+            cores: system.cpus().len(),
+            available_memory: ByteSize::kb(system.available_memory()),
+            total_memory: ByteSize::kb(system.total_memory()),
+            governors: read_cpus("cpufreq/scaling_governor"),
+            turbo: read_turbo(),
+            on_battery: is_on_battery(),
+        }
+    }
+
+    pub(crate) fn noise(&self) -> Vec<Noise> {
+        let mut noise = vec![];
+
+        if let Some(load) = self.load_average {
+            let max_load = (self.cores.max(1) as f64 * MAX_LOAD_PER_CORE).max(1.0);
+            if load > max_load {
+                noise.push(Noise {
+                    kind: "load",
+                    message: format!(
+                        "the load average of {load:.2} is high for {} cores",
+                        self.cores
+                    ),
+                });
+            }
+        }
+
+        if (self.available_memory.as_u64() as f64)
+            < self.total_memory.as_u64() as f64 * MIN_AVAILABLE_MEMORY
+        {
+            noise.push(Noise {
+                kind: "memory",
+                message: format!(
+                    "only {} of {} memory is available",
+                    self.available_memory, self.total_memory
+                ),
+            });
+        }
+
+        if let Some(governor) = self.governors.iter().find(|g| *g != "performance") {
+            noise.push(Noise {
+                kind: "governor",
+                message: format!(
+                    "the CPU frequency governor is \"{governor}\" instead of \"performance\""
+                ),
+            });
+        }
+
+        if self.turbo == Some(true) {
+            noise.push(Noise {
+                kind: "turbo",
+                message: "turbo boost is enabled, the CPU frequency depends on its temperature"
+                    .to_string(),
+            });
+        }
+
+        if self.on_battery {
+            noise.push(Noise {
+                kind: "battery",
+                message: "the machine is running on battery".to_string(),
+            });
+        }
+
+        noise
+    }
+}
+
+/// thermal throttling events of all CPUs since boot, 0 if unknown
+pub(crate) fn throttle_count() -> u64 {
+    ["core_throttle_count", "package_throttle_count"]
+        .iter()
+        .flat_map(|counter| read_cpus(&format!("thermal_throttle/{counter}")))
+        .filter_map(|count| count.parse::<u64>().ok())
+        .sum()
+}
+
+/// the trimmed contents of a file in the directory of every CPU, only available on Linux
+fn read_cpus(file: &str) -> Vec<String> {
+    fs::read_dir("/sys/devices/system/cpu")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with("cpu") && name[3..].chars().all(|c| c.is_ascii_digit())
+        })
+        .filter_map(|entry| fs::read_to_string(entry.path().join(file)).ok())
+        .map(|value| value.trim().to_string())
+        .collect()
+}
+
+/// whether turbo boost is enabled, as reported by the Intel P-state or generic cpufreq driver
+fn read_turbo() -> Option<bool> {
+    let read = |path| fs::read_to_string(path).ok().map(|v| v.trim().to_string());
+
+    match read("/sys/devices/system/cpu/intel_pstate/no_turbo") {
+        Some(no_turbo) => Some(no_turbo == "0"),
+        None => read("/sys/devices/system/cpu/cpufreq/boost").map(|boost| boost == "1"),
+    }
+}
+
+fn is_on_battery() -> bool {
+    fs::read_dir("/sys/class/power_supply")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .any(|supply| {
+            let read = |file| fs::read_to_string(supply.path().join(file)).unwrap_or_default();
+            read("type").trim() == "Battery" && read("status").trim() == "Discharging"
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn quiet() -> Conditions {
+        Conditions {
+            load_average: Some(0.2),
+            cores: 4,
+            available_memory: ByteSize::gib(6),
+            total_memory: ByteSize::gib(8),
+            governors: vec!["performance".to_string(); 4],
+            turbo: Some(false),
+            on_battery: false,
+        }
+    }
+
+    #[test]
+    fn quiet_machine_has_no_noise() {
+        assert_eq!(quiet().noise(), vec![]);
+        assert_eq!(
+            Conditions {
+                load_average: None,
+                governors: vec![],
+                turbo: None,
+                ..quiet()
+            }
+            .noise(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn every_noisy_condition_is_reported() {
+        let conditions = Conditions {
+            load_average: Some(3.5),
+            available_memory: ByteSize::mib(500),
+            governors: vec!["performance".to_string(), "powersave".to_string()],
+            turbo: Some(true),
+            on_battery: true,
+            ..quiet()
+        };
+
+        let kinds: Vec<_> = conditions.noise().iter().map(|noise| noise.kind).collect();
+
+        assert_eq!(kinds, ["load", "memory", "governor", "turbo", "battery"]);
+    }
+}
@@ -0,0 +1,325 @@
+use crate::benchmark::CgroupUsage;
+use anyhow::{anyhow, Context, Result};
+use bytesize::ByteSize;
+use colored::*;
+use libc::{c_int, close, kill, open, pid_t, write, O_CLOEXEC, O_WRONLY, SIGKILL};
+use std::collections::HashMap;
+use std::ffi::{CStr, CString, OsString};
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::thread;
+use std::time::Duration;
+
+/// controllers enabled for the transient cgroups if possible, CPU usage is always accounted
+const CONTROLLERS: &[&str] = &["memory", "io"];
+
+/// a transient cgroup v2 for one run, removed together with all remaining processes when dropped
+pub(crate) struct Cgroup {
+    path: PathBuf,
+    procs: CString,
+    /// the cgroup lives in the subtree, which is only removed after it
+    _subtree: Arc<Subtree>,
+}
+
+impl Cgroup {
+    /// creates a cgroup `benchie-<pid>/run-<n>` below the cgroup of benchie, which has to be
+    /// delegated to the user
+    pub(crate) fn create() -> Result<Self> {
+        static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+        let subtree = Subtree::shared()?;
+        let path = subtree
+            .path
+            .join(format!("run-{}", CREATED.fetch_add(1, Ordering::SeqCst)));
+
+        fs::create_dir(&path)
+            .with_context(|| format!("failed to create cgroup {}", path.display()))?;
+
+        let procs = CString::new(path.join("cgroup.procs").as_os_str().as_bytes())
+            .expect("cgroup path contains no null bytes");
+
+        Ok(Self {
+            path,
+            procs,
+            _subtree: subtree,
+        })
+    }
+
+    /// path of `cgroup.procs`, to move the command into the cgroup with `join`
+    pub(crate) fn procs(&self) -> CString {
+        self.procs.clone()
+    }
+
+    /// usage of all processes which ever were in the cgroup, a controller's values are missing
+    /// if it is not enabled
+    pub(crate) fn usage(&self) -> CgroupUsage {
+        let cpu = read_keyed(&self.path.join("cpu.stat"));
+        let cpu_time = |key| cpu.get(key).copied().map(Duration::from_micros);
+
+        let memory_peak = fs::read_to_string(self.path.join("memory.peak"))
+            .ok()
+            .and_then(|peak| peak.trim().parse().ok())
+            .map(ByteSize::b);
+
+        // one line per device like `8:0 rbytes=1024 wbytes=0 rios=1 wios=0 dbytes=0 dios=0`
+        let io = fs::read_to_string(self.path.join("io.stat")).ok();
+        let io_total = |key: &str| {
+            io.as_ref().map(|io| {
+                io.split_whitespace()
+                    .filter_map(|field| field.strip_prefix(key)?.strip_prefix('='))
+                    .filter_map(|value| value.parse::<u64>().ok())
+                    .sum::<u64>()
+            })
+        };
+
+        CgroupUsage {
+            cgroup_cpu_time: cpu_time("usage_usec"),
+            cgroup_user_time: cpu_time("user_usec"),
+            cgroup_system_time: cpu_time("system_usec"),
+            cgroup_memory_peak: memory_peak,
+            cgroup_read_bytes: io_total("rbytes").map(ByteSize::b),
+            cgroup_write_bytes: io_total("wbytes").map(ByteSize::b),
+            cgroup_read_ops: io_total("rios").map(|ops| ops as i64),
+            cgroup_write_ops: io_total("wios").map(|ops| ops as i64),
+        }
+    }
+
+    fn kill_remaining(&self) {
+        // cgroup.kill is only available since Linux 5.14
+        if fs::write(self.path.join("cgroup.kill"), "1").is_ok() {
+            return;
+        }
+
+        let procs = fs::read_to_string(self.path.join("cgroup.procs")).unwrap_or_default();
+        for pid in procs.lines().filter_map(|pid| pid.parse::<pid_t>().ok()) {
+            unsafe {
+                kill(pid, SIGKILL);
+            }
+        }
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        self.kill_remaining();
+
+        // the cgroup can only be removed once the killed processes are gone
+        for _ in 0..100 {
+            if fs::remove_dir(&self.path).is_ok() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+/// the cgroup `benchie-<pid>` holding the cgroups of the runs and a leaf `self` for benchie
+///
+/// controllers can only be enabled for the runs in a cgroup without processes, so benchie has to
+/// leave the cgroup it was started in, it moves back once the subtree is dropped
+pub(crate) struct Subtree {
+    path: PathBuf,
+    parent: PathBuf,
+    /// controllers benchie enabled in its original cgroup, which is only possible if it was the
+    /// only process in it
+    enabled_in_parent: Vec<&'static str>,
+    /// the other cgroups in the original cgroup before the controllers were enabled
+    siblings: Vec<OsString>,
+    missing_controllers: Vec<&'static str>,
+}
+
+impl Subtree {
+    /// the subtree shared by all cgroups and holders which currently exist, held for all runs
+    /// of a benchmark to set it up only once
+    pub(crate) fn shared() -> Result<Arc<Self>> {
+        static SHARED: Mutex<Weak<Subtree>> = Mutex::new(Weak::new());
+
+        let mut shared = SHARED.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(subtree) = shared.upgrade() {
+            return Ok(subtree);
+        }
+
+        let subtree = Arc::new(Self::create()?);
+        *shared = Arc::downgrade(&subtree);
+        Ok(subtree)
+    }
+
+    fn create() -> Result<Self> {
+        let parent = own_cgroup()?;
+        let path = parent.join(format!("benchie-{}", process::id()));
+        let siblings = child_cgroups(&parent);
+
+        fs::create_dir(&path).with_context(|| {
+            format!(
+                "failed to create cgroup {}, cgroups might not be delegated to your user",
+                path.display()
+            )
+        })?;
+
+        let leaf = path.join("self");
+        if let Err(error) = fs::create_dir(&leaf).and_then(|_| move_into(&leaf)) {
+            let _ = fs::remove_dir(&leaf);
+            let _ = fs::remove_dir(&path);
+            return Err(error).context("failed to move benchie into its own cgroup");
+        }
+
+        let mut subtree = Self {
+            path,
+            parent,
+            enabled_in_parent: vec![],
+            siblings,
+            missing_controllers: vec![],
+        };
+
+        for &controller in CONTROLLERS {
+            if !subtree.available_controllers().contains(controller)
+                && enable(&subtree.parent, controller).is_ok()
+            {
+                subtree.enabled_in_parent.push(controller);
+            }
+            if enable(&subtree.path, controller).is_err() {
+                subtree.missing_controllers.push(controller);
+            }
+        }
+
+        Ok(subtree)
+    }
+
+    /// the controllers which could not be enabled, their usage is missing
+    pub(crate) fn missing_controllers(&self) -> &[&'static str] {
+        &self.missing_controllers
+    }
+
+    fn available_controllers(&self) -> String {
+        fs::read_to_string(self.path.join("cgroup.controllers")).unwrap_or_default()
+    }
+}
+
+impl Drop for Subtree {
+    fn drop(&mut self) {
+        for controller in &self.enabled_in_parent {
+            let _ = disable(&self.path, controller);
+        }
+
+        // new cgroups next to benchie's might rely on the controllers benchie enabled, which
+        // another process can not tell apart from enabling them itself
+        let own = self.path.file_name().unwrap_or_default();
+        let new_siblings = child_cgroups(&self.parent)
+            .into_iter()
+            .any(|child| child != own && !self.siblings.contains(&child));
+        if !self.enabled_in_parent.is_empty() && new_siblings {
+            println!(
+                "{}",
+                format!(
+                    "warning: cgroups were created in {} during the runs => the {} controllers stay enabled and benchie stays in {}",
+                    self.parent.display(),
+                    self.enabled_in_parent.join(" and "),
+                    self.path.join("self").display()
+                )
+                .yellow()
+            );
+            return;
+        }
+
+        // processes are only allowed in the original cgroup again without these controllers
+        for controller in &self.enabled_in_parent {
+            let _ = disable(&self.parent, controller);
+        }
+
+        if move_into(&self.parent).is_ok() {
+            let _ = fs::remove_dir(self.path.join("self"));
+            let _ = fs::remove_dir(&self.path);
+        }
+    }
+}
+
+/// the names of the child cgroups of the cgroup
+fn child_cgroups(cgroup: &Path) -> Vec<OsString> {
+    fs::read_dir(cgroup)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+                .map(|entry| entry.file_name())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// moves benchie with all its threads into the cgroup
+fn move_into(cgroup: &Path) -> io::Result<()> {
+    // "0" is the writing process
+    fs::write(cgroup.join("cgroup.procs"), "0")
+}
+
+/// enables the controller for the children of the cgroup
+fn enable(cgroup: &Path, controller: &str) -> io::Result<()> {
+    fs::write(
+        cgroup.join("cgroup.subtree_control"),
+        format!("+{controller}"),
+    )
+}
+
+fn disable(cgroup: &Path, controller: &str) -> io::Result<()> {
+    fs::write(
+        cgroup.join("cgroup.subtree_control"),
+        format!("-{controller}"),
+    )
+}
+
+/// moves the current process into the cgroup, safe to be called between fork and exec
+pub(crate) fn join(procs: &CStr) -> io::Result<()> {
+    let check = |result: c_int| match result {
+        -1 => Err(io::Error::last_os_error()),
+        result => Ok(result),
+    };
+
+    let fd = check(unsafe { open(procs.as_ptr(), O_WRONLY | O_CLOEXEC) })?;
+    // "0" is the writing process
+    let written = unsafe { write(fd, b"0".as_ptr().cast(), 1) };
+    unsafe { close(fd) };
+
+    check(written as c_int).map(|_| ())
+}
+
+/// the directory of the cgroup of benchie in the cgroup v2 hierarchy
+fn own_cgroup() -> Result<PathBuf> {
+    // fields are separated by " - " from the file system type, e.g.
+    // `42 32 0:38 / /sys/fs/cgroup rw,relatime - cgroup2 cgroup2 rw`
+    let mount = fs::read_to_string("/proc/self/mountinfo")
+        .context("failed to read mounts")?
+        .lines()
+        .find_map(|line| {
+            let (mount, fs_type) = line.split_once(" - ")?;
+            fs_type
+                .starts_with("cgroup2 ")
+                .then(|| mount.split(' ').nth(4).map(PathBuf::from))?
+        })
+        .ok_or_else(|| anyhow!("cgroup v2 is not mounted"))?;
+
+    // the line of cgroup v2 has the hierarchy ID 0 and no controllers, e.g. `0::/user.slice`
+    let cgroup = fs::read_to_string("/proc/self/cgroup")
+        .context("failed to read cgroup of benchie")?
+        .lines()
+        .find_map(|line| line.strip_prefix("0::").map(str::to_string))
+        .ok_or_else(|| anyhow!("benchie is not in a cgroup v2"))?;
+
+    Ok(mount.join(cgroup.trim_start_matches('/')))
+}
+
+/// a file with lines of keys and numbers like `usage_usec 1234`
+fn read_keyed(path: &Path) -> HashMap<String, u64> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            Some((key.to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
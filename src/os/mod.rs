@@ -1,3 +1,12 @@
+use crate::benchmark::ExecutionOptions;
+use anyhow::Result;
+
+#[cfg(target_os = "linux")]
+mod cgroup;
+#[cfg(target_os = "linux")]
+mod perf;
+#[cfg(target_os = "linux")]
+mod proc;
 #[cfg(unix)]
 mod unix;
 #[cfg(windows)]
@@ -7,3 +16,42 @@ mod windows;
 pub use crate::os::windows::execute_and_measure;
 #[cfg(unix)]
 pub use unix::execute_and_measure;
+
+/// fails if performance counters are requested but can not be collected
+pub fn check_counters(options: &ExecutionOptions) -> Result<()> {
+    if !options.counters {
+        return Ok(());
+    }
+
+    #[cfg(target_os = "linux")]
+    return perf::PerfCounters::open().map(|_| ());
+
+    #[cfg(not(target_os = "linux"))]
+    anyhow::bail!("performance counters are only supported on Linux");
+}
+
+/// keeps the setup of the cgroups of the runs while held, so it is done once for all runs
+#[derive(Default)]
+pub struct CgroupGuard {
+    #[cfg(target_os = "linux")]
+    _subtree: Option<std::sync::Arc<cgroup::Subtree>>,
+    /// the controllers which could not be enabled, their usage is missing
+    pub missing_controllers: Vec<&'static str>,
+}
+
+/// fails if a cgroup is requested but can not be created, otherwise returns the guard to hold
+/// during the runs
+pub fn check_cgroup(options: &ExecutionOptions) -> Result<CgroupGuard> {
+    if !options.cgroup {
+        return Ok(CgroupGuard::default());
+    }
+
+    #[cfg(target_os = "linux")]
+    return cgroup::Subtree::shared().map(|subtree| CgroupGuard {
+        missing_controllers: subtree.missing_controllers().to_vec(),
+        _subtree: Some(subtree),
+    });
+
+    #[cfg(not(target_os = "linux"))]
+    anyhow::bail!("cgroups are only supported on Linux");
+}
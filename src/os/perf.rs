@@ -0,0 +1,156 @@
+use crate::benchmark::Counters;
+use anyhow::{bail, Result};
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::unix::io::FromRawFd;
+
+const PERF_TYPE_HARDWARE: u32 = 0;
+const PERF_TYPE_SOFTWARE: u32 = 1;
+
+const PERF_COUNT_HW_CPU_CYCLES: u64 = 0;
+const PERF_COUNT_HW_INSTRUCTIONS: u64 = 1;
+const PERF_COUNT_HW_CACHE_MISSES: u64 = 3;
+const PERF_COUNT_HW_BRANCH_MISSES: u64 = 5;
+
+const PERF_COUNT_SW_TASK_CLOCK: u64 = 1;
+const PERF_COUNT_SW_PAGE_FAULTS: u64 = 2;
+const PERF_COUNT_SW_CONTEXT_SWITCHES: u64 = 3;
+const PERF_COUNT_SW_CPU_MIGRATIONS: u64 = 4;
+
+const PERF_FORMAT_TOTAL_TIME_ENABLED: u64 = 1;
+const PERF_FORMAT_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+
+const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 1 << 3;
+
+// bits of the flags of `perf_event_attr`
+const DISABLED: u64 = 1;
+const INHERIT: u64 = 1 << 1;
+const EXCLUDE_KERNEL: u64 = 1 << 5;
+const EXCLUDE_HV: u64 = 1 << 6;
+const ENABLE_ON_EXEC: u64 = 1 << 12;
+
+/// the first version of `struct perf_event_attr` from `linux/perf_event.h`, which is
+/// accepted by all kernels supporting `perf_event_open`
+#[repr(C)]
+#[derive(Default)]
+struct PerfEventAttr {
+    kind: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+}
+
+/// an event counting for all processes spawned by the current thread, from their exec on
+struct Counter(File);
+
+impl Counter {
+    fn open(kind: u32, config: u64) -> io::Result<Self> {
+        let attr = PerfEventAttr {
+            kind,
+            size: std::mem::size_of::<PerfEventAttr>() as u32,
+            config,
+            read_format: PERF_FORMAT_TOTAL_TIME_ENABLED | PERF_FORMAT_TOTAL_TIME_RUNNING,
+            // counting only in user space is permitted with perf_event_paranoid up to 2
+            flags: DISABLED | INHERIT | EXCLUDE_KERNEL | EXCLUDE_HV | ENABLE_ON_EXEC,
+            ..Default::default()
+        };
+
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_perf_event_open,
+                &attr as *const PerfEventAttr,
+                0,
+                -1,
+                -1,
+                PERF_FLAG_FD_CLOEXEC,
+            )
+        };
+
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self(unsafe { File::from_raw_fd(fd as i32) }))
+    }
+
+    /// the value scaled by the time the event was actually counting, if it was multiplexed
+    fn read(&mut self) -> io::Result<i64> {
+        let mut buffer = [0_u8; 24];
+        self.0.read_exact(&mut buffer)?;
+
+        let [value, enabled, running] = [0, 1, 2]
+            .map(|i| u64::from_ne_bytes(buffer[i * 8..(i + 1) * 8].try_into().expect("8 bytes")));
+
+        let value = match (enabled, running) {
+            (_, 0) => 0,
+            (enabled, running) if running < enabled => {
+                (value as f64 * enabled as f64 / running as f64) as u64
+            }
+            _ => value,
+        };
+
+        Ok(value as i64)
+    }
+}
+
+/// counters for the next process spawned by the current thread
+pub(crate) struct PerfCounters {
+    instructions: Option<Counter>,
+    cycles: Option<Counter>,
+    cache_misses: Option<Counter>,
+    branch_misses: Option<Counter>,
+    task_clock: Option<Counter>,
+    context_switches: Option<Counter>,
+    cpu_migrations: Option<Counter>,
+    page_faults: Option<Counter>,
+}
+
+impl PerfCounters {
+    /// opens all counters which are available, hardware counters are missing e.g. in
+    /// virtual machines, in which case only the software counters are collected
+    pub(crate) fn open() -> Result<Self> {
+        let hardware = |config| Counter::open(PERF_TYPE_HARDWARE, config).ok();
+        let software = |config| Counter::open(PERF_TYPE_SOFTWARE, config);
+
+        // the task clock is always available, unless perf events are not permitted at all
+        let task_clock = match software(PERF_COUNT_SW_TASK_CLOCK) {
+            Ok(counter) => counter,
+            Err(error) => bail!(
+                "performance counters are not available ({error}), check /proc/sys/kernel/perf_event_paranoid or the permissions of your container"
+            ),
+        };
+
+        Ok(Self {
+            instructions: hardware(PERF_COUNT_HW_INSTRUCTIONS),
+            cycles: hardware(PERF_COUNT_HW_CPU_CYCLES),
+            cache_misses: hardware(PERF_COUNT_HW_CACHE_MISSES),
+            branch_misses: hardware(PERF_COUNT_HW_BRANCH_MISSES),
+            task_clock: Some(task_clock),
+            context_switches: software(PERF_COUNT_SW_CONTEXT_SWITCHES).ok(),
+            cpu_migrations: software(PERF_COUNT_SW_CPU_MIGRATIONS).ok(),
+            page_faults: software(PERF_COUNT_SW_PAGE_FAULTS).ok(),
+        })
+    }
+
+    /// reads the counters after the process exited
+    pub(crate) fn read(self) -> Result<Counters> {
+        let read = |counter: Option<Counter>| counter.map(|mut c| c.read()).transpose();
+
+        Ok(Counters {
+            instructions: read(self.instructions)?,
+            cycles: read(self.cycles)?,
+            cache_misses: read(self.cache_misses)?,
+            branch_misses: read(self.branch_misses)?,
+            task_clock: read(self.task_clock)?,
+            context_switches: read(self.context_switches)?,
+            cpu_migrations: read(self.cpu_migrations)?,
+            page_faults: read(self.page_faults)?,
+        })
+    }
+}
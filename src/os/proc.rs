@@ -0,0 +1,151 @@
+use crate::benchmark::IoAccounting;
+use crate::timeline::Sample;
+use bytesize::ByteSize;
+use libc::{
+    id_t, pid_t, siginfo_t, sysconf, waitid, _SC_CLK_TCK, _SC_PAGESIZE, EINTR, P_PID, WEXITED,
+    WNOWAIT,
+};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::mem::MaybeUninit;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// samples the process tree of a command from `/proc` in the background
+pub(crate) struct Sampler {
+    stop: Sender<()>,
+    thread: JoinHandle<Vec<Sample>>,
+}
+
+impl Sampler {
+    /// starts sampling immediately and then after every interval
+    ///
+    /// every sample reads the `stat` file of every process in `/proc` to find the descendants, so
+    /// it costs several milliseconds per thousand processes on the system, spent on a core the
+    /// command may also want to use
+    pub(crate) fn start(pid: pid_t, started: Instant, interval: Duration) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        let thread = thread::spawn(move || {
+            let mut samples = vec![];
+
+            loop {
+                samples.push(sample(pid, started.elapsed()));
+
+                match stopped.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => break,
+                }
+            }
+
+            samples
+        });
+
+        Self { stop, thread }
+    }
+
+    /// stops sampling and returns the samples in chronological order
+    pub(crate) fn stop(self) -> Vec<Sample> {
+        let _ = self.stop.send(());
+        self.thread.join().expect("sampling should not panic")
+    }
+}
+
+/// state of one process from `/proc/<pid>/stat`
+struct Stat {
+    parent: pid_t,
+    /// user and system time in clock ticks, including that of reaped children, so the time
+    /// of descendants which already exited is not lost
+    ticks: u64,
+    /// resident set size in pages
+    rss: u64,
+}
+
+impl Stat {
+    fn read(pid: pid_t) -> Option<Self> {
+        let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+
+        // the command name in parentheses may contain spaces, the fields start after it
+        let fields: Vec<_> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+        let field = |i: usize| fields.get(i)?.parse::<u64>().ok();
+
+        Some(Self {
+            parent: field(1)? as pid_t,
+            ticks: field(11)? + field(12)? + field(13)? + field(14)?,
+            rss: field(21)?,
+        })
+    }
+}
+
+/// the RSS and CPU time of the process and all its descendants
+fn sample(pid: pid_t, elapsed: Duration) -> Sample {
+    let stats: HashMap<pid_t, Stat> = fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+        .filter_map(|pid| Some((pid, Stat::read(pid)?)))
+        .collect();
+
+    let mut tree = vec![pid];
+    let mut i = 0;
+    while i < tree.len() {
+        let parent = tree[i];
+        tree.extend(
+            stats
+                .iter()
+                .filter(|(_, stat)| stat.parent == parent)
+                .map(|(pid, _)| *pid),
+        );
+        i += 1;
+    }
+
+    let (ticks, pages) = tree
+        .iter()
+        .filter_map(|pid| stats.get(pid))
+        .fold((0, 0), |(ticks, pages), stat| {
+            (ticks + stat.ticks, pages + stat.rss)
+        });
+
+    let (ticks_per_second, page_size) = unsafe { (sysconf(_SC_CLK_TCK), sysconf(_SC_PAGESIZE)) };
+
+    Sample {
+        elapsed,
+        rss: pages * page_size as u64,
+        cpu_time: Duration::from_secs_f64(ticks as f64 / ticks_per_second as f64),
+    }
+}
+
+/// blocks until the process exited, but keeps it as zombie so `/proc/<pid>` can still be read
+pub(crate) fn wait_for_exit(pid: pid_t) {
+    let mut info = MaybeUninit::<siginfo_t>::uninit();
+
+    while unsafe { waitid(P_PID, pid as id_t, info.as_mut_ptr(), WEXITED | WNOWAIT) } == -1
+        && io::Error::last_os_error().raw_os_error() == Some(EINTR)
+    {}
+}
+
+/// I/O of an exited process from `/proc/<pid>/io`, which includes the I/O of all descendants
+/// it reaped, `None` if the kernel does not account I/O
+pub(crate) fn read_io(pid: pid_t) -> Option<IoAccounting> {
+    let io = fs::read_to_string(format!("/proc/{pid}/io")).ok()?;
+
+    let fields: HashMap<_, _> = io
+        .lines()
+        .filter_map(|line| line.split_once(": "))
+        .filter_map(|(name, value)| Some((name, value.trim().parse::<u64>().ok()?)))
+        .collect();
+    let bytes = |name| fields.get(name).copied().map(ByteSize::b);
+    let count = |name| fields.get(name).map(|&count| count as i64);
+
+    Some(IoAccounting {
+        rchar: bytes("rchar"),
+        wchar: bytes("wchar"),
+        syscr: count("syscr"),
+        syscw: count("syscw"),
+        read_bytes: bytes("read_bytes"),
+        write_bytes: bytes("write_bytes"),
+    })
+}
@@ -1,139 +1,593 @@
-use crate::benchmark::parse_tags_from_stdout;
+use crate::benchmark::{parse_tags_from_stdout, ExecutionOptions, ResourceLimits, Scheduling};
+use crate::input::Input;
+#[cfg(target_os = "linux")]
+use crate::os::cgroup::{self, Cgroup};
+#[cfg(target_os = "linux")]
+use crate::os::perf::PerfCounters;
+#[cfg(target_os = "linux")]
+use crate::os::proc::{self, Sampler};
+use crate::output::CommandOutput;
+use crate::timeline::Timeline;
 use crate::ExecutionResult;
 use anyhow::{ensure, Context, Result};
 use libc::{
-    c_char, c_int, close, pid_t, pipe, posix_spawn_file_actions_addclose,
-    posix_spawn_file_actions_adddup2, posix_spawn_file_actions_init, posix_spawn_file_actions_t,
-    posix_spawnattr_init, posix_spawnattr_t, posix_spawnp, rusage, timeval, wait4,
+    c_int, close, getpgrp, kill, open, pid_t, rlim_t, rlimit, rusage, setrlimit, signal, tcgetpgrp,
+    tcsetpgrp, timeval, wait4, O_CLOEXEC, O_RDWR, RLIMIT_AS, RLIMIT_CPU, RLIMIT_NOFILE, SIGCONT,
+    SIGINT, SIGKILL, SIGTTOU, SIG_IGN, WCOREDUMP, WEXITSTATUS, WIFEXITED, WIFSIGNALED, WTERMSIG,
 };
 use std::collections::HashMap;
-use std::ffi::CString;
+use std::fs::File;
+use std::io::{self, Read, Write};
 use std::mem::MaybeUninit;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
-use std::{env, fs::File, io::Read, os::unix::io::FromRawFd};
 
 pub fn execute_and_measure(
     command_and_flags: &[String],
-) -> Result<(ExecutionResult, HashMap<String, String>)> {
+    options: &ExecutionOptions,
+) -> Result<(ExecutionResult, HashMap<String, String>, CommandOutput)> {
     ensure!(
         !command_and_flags.is_empty(),
         "command can not be empty for benchmarking"
     );
 
-    let command_and_flags = command_and_flags
-        .iter()
-        .map(|str| CString::new(str.as_str()))
-        .collect::<Result<Vec<_>, _>>()
-        .context("invalid string encoding for user command and flags")?;
+    if let Some(cwd) = &options.cwd {
+        ensure!(
+            cwd.is_dir(),
+            "working directory \"{}\" does not exist",
+            cwd.display()
+        );
+    }
+
+    let limits = Limits::new(&options.limits);
+    let schedule = Schedule::new(&options.scheduling)?;
+
+    let stdin = match &options.input {
+        Some(Input::File(path)) => Stdio::from(
+            File::open(path).with_context(|| format!("failed to open input {}", path.display()))?,
+        ),
+        Some(Input::Null) => Stdio::null(),
+        None => Stdio::inherit(),
+    };
+
+    let mut process = Command::new(&command_and_flags[0]);
+    process
+        .args(&command_and_flags[1..])
+        .env_clear()
+        .envs(options.environment())
+        .stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(if options.capture_stderr {
+            Stdio::piped()
+        } else if options.quiet {
+            Stdio::null()
+        } else {
+            Stdio::inherit()
+        });
+
+    // own process group, to be able to kill a timed out command together with its children
+    if options.timeout.is_some() {
+        process.process_group(0);
+    }
+
+    if let Some(cwd) = &options.cwd {
+        process.current_dir(cwd);
+    }
+
+    #[cfg(target_os = "linux")]
+    let cgroup = options.cgroup.then(Cgroup::create).transpose()?;
+    #[cfg(target_os = "linux")]
+    let cgroup_procs = cgroup.as_ref().map(Cgroup::procs);
+    #[cfg(not(target_os = "linux"))]
+    ensure!(!options.cgroup, "cgroups are only supported on Linux");
+
+    // code between fork and exec prevents spawning with posix_spawn, which takes less time
+    #[cfg(target_os = "linux")]
+    let needs_pre_exec = cgroup_procs.is_some();
+    #[cfg(not(target_os = "linux"))]
+    let needs_pre_exec = false;
 
-    let envs: Vec<_> = env::vars()
-        .map(|(key, value)| CString::new(format!("{key}={value}")))
-        .collect::<Result<Vec<_>, _>>()
-        .context("invalid string encoding of environment variables")?;
+    if needs_pre_exec || !options.limits.is_empty() || !options.scheduling.is_empty() {
+        unsafe {
+            // only async-signal-safe calls are allowed between fork and exec
+            process.pre_exec(move || {
+                #[cfg(target_os = "linux")]
+                if let Some(procs) = &cgroup_procs {
+                    cgroup::join(procs)?;
+                }
+                limits.apply()?;
+                schedule.apply()
+            });
+        }
+    }
+
+    // opened right before spawning, as they are inherited by all children of this thread
+    #[cfg(target_os = "linux")]
+    let counters = options.counters.then(PerfCounters::open).transpose()?;
+
+    let now = Instant::now();
+
+    let mut child = process
+        .spawn()
+        .context("spawning of process failed for execution of command")?;
+    let pid = child.id() as pid_t;
+
+    // given back when dropped after the command finished
+    let terminal = options.timeout.and_then(|_| Terminal::hand_over(pid));
+
+    #[cfg(target_os = "linux")]
+    let sampler = options
+        .sample_interval
+        .map(|interval| Sampler::start(pid, now, interval));
+    #[cfg(not(target_os = "linux"))]
+    ensure!(
+        options.sample_interval.is_none(),
+        "sampling memory and CPU usage is only supported on Linux"
+    );
+
+    let timed_out = Arc::new(AtomicBool::new(false));
+    let (finished, watchdog) = match options.timeout {
+        Some(timeout) => {
+            let (finished, wait_for_finish) = mpsc::channel::<()>();
+            let timed_out = timed_out.clone();
 
-    let program = &command_and_flags[0];
+            let watchdog = thread::spawn(move || {
+                if wait_for_finish.recv_timeout(timeout).is_err() {
+                    timed_out.store(true, Ordering::SeqCst);
+                    unsafe {
+                        kill(-pid, SIGKILL);
+                    }
+                }
+            });
 
-    let mut pid = MaybeUninit::<pid_t>::uninit();
-    let mut file_actions = MaybeUninit::<posix_spawn_file_actions_t>::uninit();
-    let mut spawnattr = MaybeUninit::<posix_spawnattr_t>::uninit();
+            (Some(finished), Some(watchdog))
+        }
+        None => (None, None),
+    };
+
+    // read the output while waiting, otherwise a command filling the pipe never finishes
+    let quiet = options.quiet;
+    let (read, reading_done) = mpsc::channel::<()>();
+    let stdout_reader = Reader::start(
+        child.stdout.take().expect("stdout is piped"),
+        (!quiet).then(io::stdout),
+        read.clone(),
+    );
+    let stderr_reader = child
+        .stderr
+        .take()
+        .map(|stderr| Reader::start(stderr, (!quiet).then(io::stderr), read));
 
     let mut status = MaybeUninit::<c_int>::uninit();
     let mut rusage = MaybeUninit::<rusage>::uninit();
 
-    let envp = create_null_terminated(&envs);
-    let argv = create_null_terminated(&command_and_flags);
+    // the I/O of the command includes that of its reaped descendants, but is gone once it is reaped
+    #[cfg(target_os = "linux")]
+    let (exited, io) = {
+        proc::wait_for_exit(pid);
+        (Instant::now(), proc::read_io(pid).unwrap_or_default())
+    };
+
+    let result = unsafe { wait4(pid, status.as_mut_ptr(), 0, rusage.as_mut_ptr()) };
+    let had_terminal = terminal.is_some();
+    drop(terminal);
 
-    let mut pipe_file_descriptors = [0, 0];
+    #[cfg(not(target_os = "linux"))]
+    let (exited, io) = (Instant::now(), crate::benchmark::IoAccounting::default());
+    let real_time = exited.duration_since(now);
 
-    unsafe {
-        ensure!(posix_spawn_file_actions_init(file_actions.as_mut_ptr()) == 0);
-        ensure!(posix_spawnattr_init(spawnattr.as_mut_ptr()) == 0);
+    // detached descendants keep the output open until they are done, so they are measured in the
+    // cgroup before it is dropped, but daemons which left the process group and the cgroup are
+    // not killed by the timeout, so the output is only awaited until then
+    let readers = 1 + usize::from(stderr_reader.is_some());
+    let read_to_end = (0..readers).all(|_| match options.timeout {
+        Some(timeout) => {
+            let remaining = (now + timeout).saturating_duration_since(Instant::now());
+            reading_done.recv_timeout(remaining).is_ok()
+        }
+        None => reading_done.recv().is_ok(),
+    });
+    if !read_to_end {
+        timed_out.store(true, Ordering::SeqCst);
+    }
 
-        ensure!(pipe(pipe_file_descriptors.as_mut_ptr()) != -1);
+    // processes which are still running in the cgroup are killed when it is dropped
+    #[cfg(target_os = "linux")]
+    let cgroup = cgroup.map(|cgroup| cgroup.usage()).unwrap_or_default();
+    #[cfg(not(target_os = "linux"))]
+    let cgroup = crate::benchmark::CgroupUsage::default();
 
-        // tell spawned process to close unused read end of pipe
-        ensure!(
-            posix_spawn_file_actions_addclose(file_actions.as_mut_ptr(), pipe_file_descriptors[0])
-                == 0
-        );
+    #[cfg(target_os = "linux")]
+    let timeline = Timeline::new(&sampler.map(Sampler::stop).unwrap_or_default());
+    #[cfg(not(target_os = "linux"))]
+    let timeline = Timeline::default();
+
+    if let (Some(finished), Some(watchdog)) = (finished, watchdog) {
+        let _ = finished.send(());
+        let _ = watchdog.join();
+    }
 
-        // tell spawned process to replace file descriptor 1 (stdout) with write end of the pipe
+    ensure!(
+        result == pid,
+        "could not await process after spawning a process"
+    );
+    let stdout = stdout_reader
+        .finish(read_to_end)
+        .context("failed to read output of command")?;
+
+    let stderr = stderr_reader
+        .map(|reader| reader.finish(read_to_end))
+        .transpose()
+        .context("failed to read error output of command")?;
+    let output = CommandOutput { stdout, stderr };
+
+    #[cfg(target_os = "linux")]
+    let counters = counters
+        .map(PerfCounters::read)
+        .transpose()
+        .context("failed to read performance counters")?
+        .unwrap_or_default();
+    #[cfg(not(target_os = "linux"))]
+    let counters = {
         ensure!(
-            posix_spawn_file_actions_adddup2(
-                file_actions.as_mut_ptr(),
-                pipe_file_descriptors[1],
-                1
-            ) == 0
+            !options.counters,
+            "performance counters are only supported on Linux"
         );
+        crate::benchmark::Counters::default()
+    };
 
-        let now = Instant::now();
+    let (status, rusage) = unsafe { (status.assume_init(), rusage.assume_init()) };
 
-        let result = posix_spawnp(
-            pid.as_mut_ptr(),
-            program.as_ptr(),
-            file_actions.as_mut_ptr(),
-            spawnattr.as_mut_ptr(),
-            argv.as_ptr(),
-            envp.as_ptr(),
-        );
+    let user_time = timeval_to_duration(rusage.ru_utime)?;
+    let system_time = timeval_to_duration(rusage.ru_stime)?;
 
-        close(pipe_file_descriptors[1]);
+    let signal = WIFSIGNALED(status).then(|| WTERMSIG(status));
 
-        ensure!(
-            result == 0,
-            "spawning of process failed for execution of command"
-        );
+    // Ctrl-C only reaches the command while it owns the terminal, but is meant for benchie too
+    ensure!(
+        !(had_terminal && signal == Some(SIGINT)),
+        "benchmark was interrupted"
+    );
 
-        let result = wait4(
-            pid.assume_init(),
-            status.as_mut_ptr(),
-            0,
-            rusage.as_mut_ptr(),
-        );
+    let tags_from_stdout = parse_tags_from_stdout(&output.text())?;
+
+    Ok((
+        ExecutionResult {
+            user_time,
+            system_time,
+            real_time,
+            status_code: status.into(),
+            exit_code: WIFEXITED(status).then(|| WEXITSTATUS(status).into()),
+            signal: signal.map(Into::into),
+            signal_name: signal.and_then(signal_name).map(str::to_string),
+            core_dumped: signal.map(|_| WCOREDUMP(status)),
+            timed_out: options.timeout.map(|_| timed_out.load(Ordering::SeqCst)),
+            counters,
+            timeline,
+            io,
+            cgroup,
+        },
+        tags_from_stdout,
+        output,
+    ))
+}
+
+/// reads an output of the command on its own thread, which can be left behind if the output is
+/// kept open by a descendant of the command
+struct Reader {
+    output: Arc<Mutex<Vec<u8>>>,
+    stopped: Arc<AtomicBool>,
+    thread: JoinHandle<io::Result<()>>,
+}
+
+impl Reader {
+    /// starts reading and signals `done` once the output is read to the end
+    fn start(
+        from: impl Read + Send + 'static,
+        to: Option<impl Write + Send + 'static>,
+        done: Sender<()>,
+    ) -> Self {
+        let output = Arc::new(Mutex::new(vec![]));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let (output, stopped) = (output.clone(), stopped.clone());
+            thread::spawn(move || {
+                let result = tee(from, to, &output, &stopped);
+                let _ = done.send(());
+                result
+            })
+        };
+
+        Self {
+            output,
+            stopped,
+            thread,
+        }
+    }
 
-        let real_time = now.elapsed();
+    /// the output read so far, the reader is left behind if it did not read to the end yet
+    fn finish(self, read_to_end: bool) -> io::Result<Vec<u8>> {
+        if read_to_end {
+            self.thread
+                .join()
+                .expect("reading output should not panic")?;
+        } else {
+            self.stopped.store(true, Ordering::SeqCst);
+        }
 
+        let mut output = self.output.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(std::mem::take(&mut *output))
+    }
+}
+
+/// reads the output of the command and passes it through as it arrives, so progress and the
+/// order of stdout and stderr stay visible while the output is captured
+fn tee(
+    mut from: impl Read,
+    mut to: Option<impl Write>,
+    output: &Mutex<Vec<u8>>,
+    stopped: &AtomicBool,
+) -> io::Result<()> {
+    let mut chunk = [0; 8192];
+
+    loop {
+        let read = match from.read(&mut chunk) {
+            Ok(0) => return Ok(()),
+            Ok(read) => read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+
+        // output arriving after the run was given up on belongs to no benchmark
+        if stopped.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        // the output is still read to the end if it can not be passed through
+        if let Some(writer) = &mut to {
+            if writer
+                .write_all(&chunk[..read])
+                .and_then(|_| writer.flush())
+                .is_err()
+            {
+                to = None;
+            }
+        }
+        output
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend_from_slice(&chunk[..read]);
+    }
+}
+
+/// the controlling terminal, whose foreground process group is handed over to a command in its
+/// own process group, so the command can read from it and receives Ctrl-C
+struct Terminal {
+    fd: c_int,
+    group: pid_t,
+}
+
+impl Terminal {
+    /// makes the process group of the command the foreground group, if benchie is in the
+    /// foreground of a controlling terminal
+    fn hand_over(group: pid_t) -> Option<Self> {
+        let fd = unsafe { open(c"/dev/tty".as_ptr(), O_RDWR | O_CLOEXEC) };
+        if fd == -1 {
+            return None;
+        }
+
+        let own_group = unsafe { getpgrp() };
+        if unsafe { tcgetpgrp(fd) } != own_group || unsafe { tcsetpgrp(fd, group) } != 0 {
+            unsafe { close(fd) };
+            return None;
+        }
+
+        // the command is stopped by SIGTTIN if it read from the terminal before
+        unsafe { kill(-group, SIGCONT) };
+
+        Some(Self {
+            fd,
+            group: own_group,
+        })
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        unsafe {
+            // benchie is in the background now, so taking the terminal back raises SIGTTOU
+            let handler = signal(SIGTTOU, SIG_IGN);
+            tcsetpgrp(self.fd, self.group);
+            signal(SIGTTOU, handler);
+            close(self.fd);
+        }
+    }
+}
+
+/// `setrlimit` arguments for memory, CPU time and open files, prepared before forking
+struct Limits {
+    memory: Option<rlimit>,
+    cpu_time: Option<rlimit>,
+    files: Option<rlimit>,
+}
+
+impl Limits {
+    fn new(limits: &ResourceLimits) -> Self {
+        let limit = |value: u64| rlimit {
+            rlim_cur: value as rlim_t,
+            rlim_max: value as rlim_t,
+        };
+
+        Self {
+            memory: limits.memory.map(|memory| limit(memory.as_u64())),
+            cpu_time: limits.cpu_time.map(|cpu_time| {
+                // SIGXCPU is sent at the soft limit, the hard limit a second later enforces it
+                let seconds = cpu_time.as_secs() + u64::from(cpu_time.subsec_nanos() > 0);
+                rlimit {
+                    rlim_cur: seconds as rlim_t,
+                    rlim_max: (seconds + 1) as rlim_t,
+                }
+            }),
+            files: limits.files.map(limit),
+        }
+    }
+
+    /// applies the limits to the current process, safe to be called between fork and exec
+    fn apply(&self) -> io::Result<()> {
+        let limits = [
+            (RLIMIT_AS, self.memory),
+            (RLIMIT_CPU, self.cpu_time),
+            (RLIMIT_NOFILE, self.files),
+        ];
+
+        for (resource, limit) in limits {
+            if let Some(limit) = limit {
+                if unsafe { setrlimit(resource, &limit) } != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// scheduling system calls for CPUs, priority and ASLR, prepared before forking
+struct Schedule {
+    nice: Option<c_int>,
+    #[cfg(target_os = "linux")]
+    cpus: Option<libc::cpu_set_t>,
+    #[cfg(target_os = "linux")]
+    policy: Option<(c_int, libc::sched_param)>,
+    #[cfg(target_os = "linux")]
+    no_aslr: bool,
+}
+
+impl Schedule {
+    #[cfg(target_os = "linux")]
+    fn new(scheduling: &Scheduling) -> Result<Self> {
+        use crate::benchmark::SchedPolicy;
+
+        let cpus = scheduling
+            .cpus
+            .as_ref()
+            .map(|cpus| {
+                let mut set = unsafe { std::mem::zeroed::<libc::cpu_set_t>() };
+                for &cpu in cpus {
+                    ensure!(
+                        cpu < libc::CPU_SETSIZE as usize,
+                        "CPU {cpu} exceeds the maximum CPU {}",
+                        libc::CPU_SETSIZE - 1
+                    );
+                    unsafe { libc::CPU_SET(cpu, &mut set) };
+                }
+                Ok(set)
+            })
+            .transpose()?;
+
+        let policy = scheduling.policy.map(|policy| {
+            let policy = match policy {
+                SchedPolicy::Fifo => libc::SCHED_FIFO,
+                SchedPolicy::RoundRobin => libc::SCHED_RR,
+            };
+            let param = libc::sched_param {
+                sched_priority: unsafe { libc::sched_get_priority_min(policy) },
+            };
+            (policy, param)
+        });
+
+        Ok(Self {
+            nice: scheduling.nice,
+            cpus,
+            policy,
+            no_aslr: scheduling.no_aslr,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn new(scheduling: &Scheduling) -> Result<Self> {
         ensure!(
-            result != 0,
-            "could not await process after spawning a process"
+            scheduling.cpus.is_none() && scheduling.policy.is_none() && !scheduling.no_aslr,
+            "CPU pinning, scheduling policies and disabling ASLR are only supported on Linux"
         );
 
-        let user_time = timeval_to_duration(rusage.assume_init().ru_utime)?;
-        let system_time = timeval_to_duration(rusage.assume_init().ru_stime)?;
+        Ok(Self {
+            nice: scheduling.nice,
+        })
+    }
+
+    /// applies the schedule to the current process, safe to be called between fork and exec
+    fn apply(&self) -> io::Result<()> {
+        let check = |result: c_int| match result {
+            -1 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        };
 
-        let mut f = File::from_raw_fd(pipe_file_descriptors[0]);
-        let mut cmd_output = String::new();
-        f.read_to_string(&mut cmd_output)?;
+        if let Some(nice) = self.nice {
+            check(unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) })?;
+        }
 
-        let tags_from_stdout = parse_tags_from_stdout(&cmd_output)?;
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(cpus) = &self.cpus {
+                let size = std::mem::size_of::<libc::cpu_set_t>();
+                check(unsafe { libc::sched_setaffinity(0, size, cpus) })?;
+            }
 
-        print!("{}", cmd_output);
+            if let Some((policy, param)) = &self.policy {
+                check(unsafe { libc::sched_setscheduler(0, *policy, param) })?;
+            }
 
-        Ok((
-            ExecutionResult {
-                user_time,
-                system_time,
-                real_time,
-                status_code: status.assume_init().into(),
-            },
-            tags_from_stdout,
-        ))
+            if self.no_aslr {
+                let persona = unsafe { libc::personality(0xffffffff) };
+                check(persona)?;
+                let persona = persona as libc::c_ulong | libc::ADDR_NO_RANDOMIZE as libc::c_ulong;
+                check(unsafe { libc::personality(persona) })?;
+            }
+        }
+
+        Ok(())
     }
 }
 
+/// the name of a signal as defined in `signal.h`, only for the signals which terminate a process
+fn signal_name(signal: c_int) -> Option<&'static str> {
+    let name = match signal {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGILL => "SIGILL",
+        libc::SIGTRAP => "SIGTRAP",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGUSR1 => "SIGUSR1",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGUSR2 => "SIGUSR2",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGXCPU => "SIGXCPU",
+        libc::SIGXFSZ => "SIGXFSZ",
+        libc::SIGVTALRM => "SIGVTALRM",
+        libc::SIGPROF => "SIGPROF",
+        libc::SIGSYS => "SIGSYS",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
 fn timeval_to_duration(value: timeval) -> Result<Duration> {
     let secs = Duration::from_secs(value.tv_sec.try_into()?);
     let micros = Duration::from_micros(value.tv_usec.try_into()?);
 
     Ok(secs + micros)
 }
-
-fn create_null_terminated(strings: &[CString]) -> Vec<*mut c_char> {
-    let mut list = vec![std::ptr::null_mut::<c_char>(); strings.len() + 1];
-
-    strings.iter().enumerate().for_each(|(i, str)| {
-        list[i] = str.as_ptr() as *mut c_char;
-    });
-
-    list
-}
@@ -1,5 +1,7 @@
+use crate::benchmark::{ExecutionOptions, Scheduling};
+use crate::output::CommandOutput;
 use crate::ExecutionResult;
-use anyhow::{ensure, Result};
+use anyhow::{bail, ensure, Result};
 use libc::c_void;
 use std::collections::HashMap;
 use std::mem::MaybeUninit;
@@ -15,12 +17,33 @@ use windows::Win32::System::Threading::{
 
 pub fn execute_and_measure(
     command_and_flags: &[String],
-) -> Result<(ExecutionResult, HashMap<String, String>)> {
+    options: &ExecutionOptions,
+) -> Result<(ExecutionResult, HashMap<String, String>, CommandOutput)> {
     ensure!(
         !command_and_flags.is_empty(),
         "command can not be empty for benchmarking"
     );
 
+    if options.cwd.is_some() || !options.env.is_empty() || options.env_clear {
+        bail!("working directory and environment variables are not supported on Windows yet");
+    }
+
+    if options.timeout.is_some() || !options.limits.is_empty() {
+        bail!("timeouts and resource limits are not supported on Windows yet");
+    }
+
+    if options.scheduling != Scheduling::default() {
+        bail!("CPU pinning, priorities and disabling ASLR are not supported on Windows yet");
+    }
+
+    if options.capture_stderr || options.quiet || options.input.is_some() {
+        bail!("redirecting input and output is not supported on Windows yet");
+    }
+
+    if options.counters || options.sample_interval.is_some() || options.cgroup {
+        bail!("performance counters, sampling and cgroups are only supported on Linux");
+    }
+
     let mut program_and_flags: Vec<u16> = dbg!(command_and_flags)
         .join(" ")
         .encode_utf16()
@@ -74,8 +97,11 @@ pub fn execute_and_measure(
             system_time,
             real_time,
             status_code: status_code.into(),
+            exit_code: Some(status_code.into()),
+            ..Default::default()
         },
         HashMap::new(),
+        CommandOutput::default(),
     ))
 }
 
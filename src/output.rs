@@ -0,0 +1,147 @@
+use crate::value;
+use anyhow::anyhow;
+use bytesize::ByteSize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+/// what the benchmarked command wrote to stdout and, if captured, to stderr
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Option<Vec<u8>>,
+}
+
+impl CommandOutput {
+    /// stdout and stderr as text, to be scanned for `@benchie` lines
+    pub(crate) fn text(&self) -> String {
+        let mut text = String::from_utf8_lossy(&self.stdout).into_owned();
+
+        if let Some(stderr) = &self.stderr {
+            text.push('\n');
+            text.push_str(&String::from_utf8_lossy(stderr));
+        }
+
+        text
+    }
+}
+
+/// how the output of every run is kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStorage {
+    /// stdout and stderr are written to `.benchie/outputs/<id>`
+    Full,
+    /// only the hash and size of stdout and stderr are stored with the benchmark
+    Hash,
+}
+
+impl OutputStorage {
+    pub const VARIANTS: &'static [&'static str] = &["full", "hash"];
+}
+
+impl FromStr for OutputStorage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(OutputStorage::Full),
+            "hash" => Ok(OutputStorage::Hash),
+            _ => Err(anyhow!(
+                "unknown output storage \"{}\", expected one of: {}",
+                s,
+                OutputStorage::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputStorage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            OutputStorage::Full => "full",
+            OutputStorage::Hash => "hash",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// hashes and sizes of the output of a run, stored with the benchmark
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputSummary {
+    /// the directory in `.benchie/outputs` with the full output
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub output_id: Option<String>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub stdout_hash: Option<String>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub stdout_size: Option<ByteSize>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub stderr_hash: Option<String>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub stderr_size: Option<ByteSize>,
+}
+
+impl OutputSummary {
+    pub(crate) fn new(output: &CommandOutput, output_id: Option<String>) -> Self {
+        Self {
+            output_id,
+            stdout_hash: Some(sha256(&output.stdout)),
+            stdout_size: Some(ByteSize::b(output.stdout.len() as u64)),
+            stderr_hash: output.stderr.as_deref().map(sha256),
+            stderr_size: output
+                .stderr
+                .as_ref()
+                .map(|stderr| ByteSize::b(stderr.len() as u64)),
+        }
+    }
+}
+
+/// the hex encoded SHA-256 hash of the bytes
+pub(crate) fn sha256(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// a random id for the outputs of a run, stored as `output_id` in its benchmark to link both
+pub(crate) fn output_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn summary_contains_hashes_and_sizes() {
+        let output = CommandOutput {
+            stdout: b"hello\n".to_vec(),
+            stderr: None,
+        };
+
+        let summary = OutputSummary::new(&output, None);
+
+        assert_eq!(
+            summary.stdout_hash.as_deref(),
+            Some("5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03")
+        );
+        assert_eq!(summary.stdout_size, Some(ByteSize::b(6)));
+        assert_eq!(summary.stderr_hash, None);
+    }
+
+    #[test]
+    fn stderr_is_scanned_after_stdout() {
+        let output = CommandOutput {
+            stdout: b"@benchie a=1".to_vec(),
+            stderr: Some(b"@benchie b=2".to_vec()),
+        };
+
+        assert_eq!(output.text(), "@benchie a=1\n@benchie b=2");
+        assert!("full".parse::<OutputStorage>().is_ok());
+        assert!("all".parse::<OutputStorage>().is_err());
+    }
+}
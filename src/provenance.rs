@@ -0,0 +1,249 @@
+use crate::value;
+use anyhow::{ensure, Context, Result};
+use bytesize::ByteSize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// path, size, modification time and hash of the benchmarked executable
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BinarySummary {
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub binary_path: Option<String>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub binary_size: Option<ByteSize>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub binary_modified: Option<DateTime<Utc>>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub binary_hash: Option<String>,
+}
+
+impl BinarySummary {
+    /// summarizes the executable as it would be found by the command, the summary is empty if
+    /// it can not be found, e.g. because it is a shell builtin
+    pub(crate) fn new(
+        program: &str,
+        cwd: Option<&Path>,
+        environment: &HashMap<String, String>,
+    ) -> Result<Self> {
+        let path = match resolve(program, cwd, environment) {
+            Some(path) => path,
+            None => return Ok(Self::default()),
+        };
+
+        let metadata = fs::metadata(&path)
+            .with_context(|| format!("failed to read metadata of {}", path.display()))?;
+        let modified = metadata.modified().ok();
+
+        Ok(Self {
+            binary_path: Some(path.display().to_string()),
+            binary_size: Some(ByteSize::b(metadata.len())),
+            binary_modified: modified.map(DateTime::<Utc>::from),
+            binary_hash: Some(hash(&path, metadata.len(), modified)?),
+        })
+    }
+}
+
+/// the absolute path of the program, relative paths are resolved against the working
+/// directory, names against the `PATH` of the command
+fn resolve(
+    program: &str,
+    cwd: Option<&Path>,
+    environment: &HashMap<String, String>,
+) -> Option<PathBuf> {
+    let cwd = match cwd {
+        Some(cwd) => cwd.to_path_buf(),
+        None => env::current_dir().ok()?,
+    };
+
+    if program.contains(std::path::MAIN_SEPARATOR) || program.contains('/') {
+        return fs::canonicalize(cwd.join(program)).ok();
+    }
+
+    let candidates = |dir: PathBuf| {
+        let path = cwd.join(dir).join(program);
+        if cfg!(windows) {
+            vec![path.with_extension("exe"), path]
+        } else {
+            vec![path]
+        }
+    };
+
+    env::split_paths(environment.get("PATH")?)
+        .flat_map(candidates)
+        .find(|path| is_executable(path))
+        .and_then(|path| fs::canonicalize(path).ok())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// SHA-256 hash of the file, which is only computed again if its size or modification time
+/// changed, as executables can be large and are benchmarked many times
+fn hash(path: &Path, size: u64, modified: Option<SystemTime>) -> Result<String> {
+    type Key = (PathBuf, u64, Option<SystemTime>);
+    static HASHES: Mutex<BTreeMap<Key, String>> = Mutex::new(BTreeMap::new());
+
+    let key = (path.to_path_buf(), size, modified);
+    if let Some(hash) = HASHES.lock().expect("not poisoned").get(&key) {
+        return Ok(hash.clone());
+    }
+
+    let mut file =
+        File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let hash = format!("{:x}", hasher.finalize());
+
+    HASHES
+        .lock()
+        .expect("not poisoned")
+        .insert(key, hash.clone());
+
+    Ok(hash)
+}
+
+/// runs the commands of the configured tools with a shell and returns the first line of their
+/// output as `tool.<name>` tags, e.g. `tool.rustc` for `rustc -V`, or why it could not be read
+pub(crate) fn tool_versions(
+    tools: &HashMap<String, String>,
+    cwd: Option<&Path>,
+    environment: &HashMap<String, String>,
+) -> HashMap<String, Result<String>> {
+    tools
+        .iter()
+        .map(|(name, command)| {
+            (
+                format!("tool.{name}"),
+                tool_version(command, cwd, environment),
+            )
+        })
+        .collect()
+}
+
+fn tool_version(
+    command: &str,
+    cwd: Option<&Path>,
+    environment: &HashMap<String, String>,
+) -> Result<String> {
+    let mut process = if cfg!(windows) {
+        let mut process = Command::new("cmd");
+        process.args(["/C", command]);
+        process
+    } else {
+        let mut process = Command::new("sh");
+        process.args(["-c", command]);
+        process
+    };
+
+    if let Some(cwd) = cwd {
+        process.current_dir(cwd);
+    }
+
+    let output = process
+        .env_clear()
+        .envs(environment)
+        .output()
+        .with_context(|| format!("failed to execute \"{command}\""))?;
+
+    ensure!(
+        output.status.success(),
+        "\"{command}\" exited with {}",
+        output.status
+    );
+
+    // some tools like `java -version` print their version to stderr
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let version = stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .to_string();
+
+    Ok(version)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn programs_are_resolved_like_the_command() {
+        let dir = tempdir().unwrap();
+        let binary = dir.path().join("app");
+        fs::write(&binary, "binary").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let binary = fs::canonicalize(binary).unwrap();
+
+        let path = HashMap::from([("PATH".to_string(), dir.path().display().to_string())]);
+
+        assert_eq!(resolve("app", None, &path), Some(binary.clone()));
+        assert_eq!(
+            resolve("./app", Some(dir.path()), &HashMap::new()),
+            Some(binary.clone())
+        );
+        assert_eq!(resolve("app", None, &HashMap::new()), None);
+        assert_eq!(resolve("missing", None, &path), None);
+
+        let summary = BinarySummary::new("app", None, &path).unwrap();
+        assert_eq!(summary.binary_size, Some(ByteSize::b(6)));
+        assert_eq!(
+            summary.binary_hash.as_deref(),
+            Some("9a3a45d01531a20e89ac6ae10b0b0beb0492acd7216a368aa062d1a5fecaf9cd")
+        );
+        assert!(summary.binary_modified.is_some());
+        assert_eq!(
+            BinarySummary::new("missing", None, &path).unwrap(),
+            BinarySummary::default()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tool_versions_are_the_first_line_of_output() {
+        let tools = HashMap::from([
+            (
+                "out".to_string(),
+                "echo; echo 'out 1.0'; echo more".to_string(),
+            ),
+            ("err".to_string(), "echo 'err 2.0' >&2".to_string()),
+        ]);
+        let environment: HashMap<_, _> = env::vars().collect();
+
+        let versions = tool_versions(&tools, None, &environment);
+        let version = |tag: &str| versions.get(tag)?.as_deref().ok();
+
+        assert_eq!(version("tool.out"), Some("out 1.0"));
+        assert_eq!(version("tool.err"), Some("err 2.0"));
+        assert!(tool_version("false", None, &environment).is_err());
+    }
+}
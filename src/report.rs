@@ -0,0 +1,474 @@
+use crate::git::read_first_parent_history;
+use crate::history::compute_history;
+use crate::show::{
+    apply_filter, check_machines, compute_2d_table_data, compute_table_data_1d, format_2d_rows,
+    group_1d_table_data,
+};
+use crate::value::{Aggregation, DisplayOptions};
+use crate::{BenchmarkRaw, Storage, Value, Values};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const HISTORY_COMMITS: usize = 50;
+const SYSTEM_KEYS: &[&str] = &[
+    "os",
+    "os_version",
+    "kernel_version",
+    "arch",
+    "cores",
+    "total_memory",
+];
+
+const SVG_WIDTH: f64 = 720.0;
+const SVG_BAR_HEIGHT: f64 = 24.0;
+const SVG_LINE_HEIGHT: f64 = 240.0;
+const SVG_LABEL_WIDTH: f64 = 200.0;
+const SVG_MARGIN: f64 = 40.0;
+
+#[allow(clippy::too_many_arguments)]
+pub fn report(
+    storage: &Storage,
+    out: &Path,
+    metrics: &[String],
+    row: &str,
+    col: Option<&str>,
+    filter: &HashMap<String, String>,
+    display: &DisplayOptions,
+    all_machines: bool,
+) -> Result<()> {
+    let benchmarks = storage.load_all_benchmarks()?;
+    let grouped_by: Vec<_> = std::iter::once(row).chain(col).collect();
+    check_machines(&benchmarks, filter, &grouped_by, all_machines)?;
+
+    // the history is optional, a report can also be generated outside of a Git repository
+    let commits = read_first_parent_history(HISTORY_COMMITS).unwrap_or_default();
+
+    let html = build_report(&benchmarks, &commits, metrics, row, col, filter, display)?;
+
+    fs::write(out, html).with_context(|| format!("failed to write report to {:?}", out))?;
+
+    println!(
+        "Written report of {} benchmarks to {}",
+        benchmarks
+            .iter()
+            .filter(|b| apply_filter(b, filter))
+            .count(),
+        out.display()
+    );
+
+    Ok(())
+}
+
+fn build_report(
+    benchmarks: &[BenchmarkRaw],
+    commits: &[(String, String)],
+    metrics: &[String],
+    row: &str,
+    col: Option<&str>,
+    filter: &HashMap<String, String>,
+    display: &DisplayOptions,
+) -> Result<String> {
+    // a chart shows a single value per entry, so values are aggregated even if not configured
+    let aggregation = display.aggregation.unwrap_or(Aggregation::Mean);
+    let display = &DisplayOptions {
+        aggregation: Some(aggregation),
+        ..*display
+    };
+    let mut body = String::new();
+
+    let filtered: Vec<_> = benchmarks
+        .iter()
+        .filter(|b| apply_filter(b, filter))
+        .cloned()
+        .collect();
+
+    writeln!(
+        body,
+        "<h1>benchie report</h1>\n<p>{} benchmarks, generated at {}{}</p>",
+        filtered.len(),
+        Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+        if filter.is_empty() {
+            String::new()
+        } else {
+            format!(
+                ", filtered by {}",
+                escape(&filter.iter().map(|(k, v)| format!("{k}={v}")).join(", "))
+            )
+        }
+    )?;
+
+    for metric in metrics {
+        writeln!(body, "<h2>{}</h2>", escape(metric))?;
+
+        let groups = group_1d_table_data(compute_table_data_1d(&filtered, row, metric, filter));
+        let aggregated: Vec<_> = groups
+            .iter()
+            .filter_map(|(label, values)| values.aggregate(aggregation).map(|v| (label.clone(), v)))
+            .collect();
+
+        if aggregated.is_empty() {
+            writeln!(
+                body,
+                "<p>no benchmarks with \"{}\" found</p>",
+                escape(metric)
+            )?;
+            continue;
+        }
+
+        writeln!(body, "<h3>{} by {}</h3>", escape(metric), escape(row))?;
+        body.push_str(&svg_bar_chart(&aggregated, display));
+
+        writeln!(body, "<h3>comparison</h3>")?;
+        match col {
+            Some(col) => body.push_str(&comparison_table_2d(
+                &filtered, row, col, metric, filter, display,
+            )),
+            None => body.push_str(&comparison_table_1d(row, &groups, display)),
+        }
+
+        let history = compute_history(&filtered, commits, metric, filter);
+        let points: Vec<_> = history
+            .iter()
+            .filter_map(|entry| {
+                entry
+                    .values
+                    .aggregate(aggregation)
+                    .map(|v| (entry.commit_id.chars().take(8).collect::<String>(), v))
+            })
+            .collect();
+
+        if !points.is_empty() {
+            writeln!(body, "<h3>history along the first-parent commits</h3>")?;
+            body.push_str(&svg_line_chart(&points, display));
+            body.push_str(&html_table(
+                &["commit", "message", "runs", metric.as_str()],
+                history.iter().map(|entry| {
+                    vec![
+                        entry.commit_id.chars().take(8).collect(),
+                        entry.commit_message.clone(),
+                        entry.values.0.len().to_string(),
+                        display.format_values(&entry.values),
+                    ]
+                }),
+            ));
+        }
+    }
+
+    writeln!(body, "<h2>systems</h2>")?;
+    body.push_str(&system_table(&filtered));
+
+    writeln!(body, "<h2>dataset</h2>")?;
+    body.push_str(DATASET_SECTION);
+
+    let dataset: Vec<BTreeMap<&String, String>> = filtered
+        .iter()
+        .map(|b| b.data.iter().map(|(k, v)| (k, v.to_string())).collect())
+        .collect();
+
+    Ok(format!(
+        include_str!("report.html"),
+        style = STYLE,
+        body = body,
+        raw = embed_json(&serde_json::to_string(&filtered)?),
+        dataset = embed_json(&serde_json::to_string(&dataset)?),
+        script = SCRIPT,
+    ))
+}
+
+fn comparison_table_1d(row: &str, groups: &[(String, Values)], display: &DisplayOptions) -> String {
+    html_table(
+        &[row, "runs", "mean", "median", "min", "max"],
+        groups.iter().map(|(label, values)| {
+            let mut cells = vec![label.clone(), values.0.len().to_string()];
+            for aggregation in [
+                Aggregation::Mean,
+                Aggregation::Median,
+                Aggregation::Min,
+                Aggregation::Max,
+            ] {
+                cells.push(
+                    values
+                        .aggregate(aggregation)
+                        .map_or_else(String::new, |v| display.format_value(&v)),
+                );
+            }
+            cells
+        }),
+    )
+}
+
+fn comparison_table_2d(
+    benchmarks: &[BenchmarkRaw],
+    row: &str,
+    col: &str,
+    metric: &str,
+    filter: &HashMap<String, String>,
+    display: &DisplayOptions,
+) -> String {
+    let data = compute_2d_table_data(benchmarks, row, col, metric, filter);
+
+    let mut headers: Vec<&str> = data.table_headers.iter().map(String::as_str).collect();
+    headers[0] = row;
+
+    html_table(
+        &headers,
+        format_2d_rows(&data, |values| display.format_values(values)).into_iter(),
+    )
+}
+
+fn system_table(benchmarks: &[BenchmarkRaw]) -> String {
+    let systems = benchmarks
+        .iter()
+        .map(|b| {
+            SYSTEM_KEYS
+                .iter()
+                .map(|key| b.data.get(*key).map_or_else(String::new, Value::to_string))
+                .collect::<Vec<_>>()
+        })
+        .counts();
+
+    let mut headers = SYSTEM_KEYS.to_vec();
+    headers.push("benchmarks");
+
+    html_table(
+        &headers,
+        systems.into_iter().sorted().map(|(mut cells, count)| {
+            cells.push(count.to_string());
+            cells
+        }),
+    )
+}
+
+fn html_table(headers: &[&str], rows: impl Iterator<Item = Vec<String>>) -> String {
+    let mut table = String::from("<table>\n<tr>");
+    for header in headers {
+        let _ = write!(table, "<th>{}</th>", escape(header));
+    }
+    table.push_str("</tr>\n");
+
+    for row in rows {
+        table.push_str("<tr>");
+        for cell in row {
+            let _ = write!(table, "<td>{}</td>", escape(&cell));
+        }
+        table.push_str("</tr>\n");
+    }
+
+    table.push_str("</table>\n");
+    table
+}
+
+fn svg_bar_chart(entries: &[(String, Value)], display: &DisplayOptions) -> String {
+    let max = entries
+        .iter()
+        .filter_map(|(_, v)| v.as_f64())
+        .fold(0.0, f64::max);
+    let plot_width = SVG_WIDTH - SVG_LABEL_WIDTH - 2.0 * SVG_MARGIN - 80.0;
+    let height = entries.len() as f64 * SVG_BAR_HEIGHT + SVG_MARGIN;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{SVG_WIDTH}\" height=\"{height}\">\n"
+    );
+
+    for (i, (label, value)) in entries.iter().enumerate() {
+        let y = SVG_MARGIN / 2.0 + i as f64 * SVG_BAR_HEIGHT;
+        let width = if max > 0.0 {
+            value.as_f64().unwrap_or(0.0) / max * plot_width
+        } else {
+            0.0
+        };
+
+        let _ = writeln!(
+            svg,
+            "<text x=\"{x}\" y=\"{ty:.1}\" text-anchor=\"end\">{label}</text>\
+             <rect x=\"{bx}\" y=\"{y}\" width=\"{width:.1}\" height=\"{h:.1}\" class=\"bar\"/>\
+             <text x=\"{vx:.1}\" y=\"{ty:.1}\">{value}</text>",
+            x = SVG_LABEL_WIDTH,
+            ty = y + SVG_BAR_HEIGHT * 0.65,
+            label = escape(label),
+            bx = SVG_LABEL_WIDTH + 8.0,
+            h = SVG_BAR_HEIGHT * 0.8,
+            vx = SVG_LABEL_WIDTH + 12.0 + width,
+            value = escape(&display.format_value(value)),
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn svg_line_chart(points: &[(String, Value)], display: &DisplayOptions) -> String {
+    let numbers: Vec<_> = points
+        .iter()
+        .map(|(_, v)| v.as_f64().unwrap_or(0.0))
+        .collect();
+    let min = numbers.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = numbers.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+    let plot_width = SVG_WIDTH - SVG_LABEL_WIDTH - SVG_MARGIN;
+    let plot_height = SVG_LINE_HEIGHT - 2.0 * SVG_MARGIN;
+
+    let x = |i: usize| {
+        if points.len() > 1 {
+            SVG_LABEL_WIDTH + i as f64 / (points.len() - 1) as f64 * plot_width
+        } else {
+            SVG_LABEL_WIDTH + plot_width / 2.0
+        }
+    };
+    let y = |v: f64| {
+        if max > min {
+            SVG_MARGIN + (max - v) / (max - min) * plot_height
+        } else {
+            SVG_MARGIN + plot_height / 2.0
+        }
+    };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{SVG_WIDTH}\" height=\"{SVG_LINE_HEIGHT}\">\n"
+    );
+
+    let template = &points[0].1;
+    for v in [max, min] {
+        let label = template
+            .with_f64(v)
+            .map_or_else(|| v.to_string(), |v| display.format_value(&v));
+        let _ = writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+            SVG_LABEL_WIDTH - 8.0,
+            y(v) + 4.0,
+            escape(&label)
+        );
+    }
+
+    let path = numbers
+        .iter()
+        .enumerate()
+        .map(|(i, v)| format!("{:.1},{:.1}", x(i), y(*v)))
+        .join(" ");
+    let _ = writeln!(svg, "<polyline points=\"{path}\" class=\"line\"/>");
+
+    for (i, ((label, value), v)) in points.iter().zip(numbers.iter()).enumerate() {
+        let _ = writeln!(
+            svg,
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"4\" class=\"point\"><title>{}: {}</title></circle>",
+            x(i),
+            y(*v),
+            escape(label),
+            escape(&display.format_value(value))
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// JSON embedded into a script tag must not be able to close the tag prematurely
+fn embed_json(json: &str) -> String {
+    json.replace('<', "\\u003c")
+}
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: right; }
+th { background: #f0f0f0; }
+td:first-child, th:first-child { text-align: left; }
+svg text { font-size: 12px; fill: #222; }
+.bar { fill: #4a7ebb; }
+.line { fill: none; stroke: #4a7ebb; stroke-width: 2; }
+.point { fill: #4a7ebb; }
+#dataset-filter { width: 30em; }";
+
+const DATASET_SECTION: &str =
+    "<p><input id=\"dataset-filter\" placeholder=\"key=value, key2=value2\"> \
+<span id=\"dataset-count\"></span></p>\n<div id=\"dataset\"></div>\n";
+
+const SCRIPT: &str = r#"const dataset = JSON.parse(document.getElementById('benchie-dataset').textContent);
+const keys = [...new Set(dataset.flatMap(b => Object.keys(b)))].sort();
+
+function render() {
+  const filters = document.getElementById('dataset-filter').value
+    .split(',')
+    .map(f => f.trim().split('='))
+    .filter(f => f.length === 2 && f[0] !== '');
+  const rows = dataset.filter(b => filters.every(([k, v]) => b[k] === v));
+
+  const table = document.createElement('table');
+  const header = table.insertRow();
+  keys.forEach(k => {
+    const th = document.createElement('th');
+    th.textContent = k;
+    header.appendChild(th);
+  });
+  rows.forEach(b => {
+    const row = table.insertRow();
+    keys.forEach(k => { row.insertCell().textContent = b[k] ?? ''; });
+  });
+
+  const container = document.getElementById('dataset');
+  container.replaceChildren(table);
+  document.getElementById('dataset-count').textContent = rows.length + ' of ' + dataset.length + ' benchmarks';
+}
+
+document.getElementById('dataset-filter').addEventListener('input', render);
+render();"#;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    fn benchmark(algorithm: &str, real_time: u64) -> BenchmarkRaw {
+        let mut b = BenchmarkRaw::default();
+        b.data.insert(
+            "algorithm".to_string(),
+            Value::String(algorithm.to_string()),
+        );
+        b.data.insert(
+            "real_time".to_string(),
+            Value::Duration(Duration::from_millis(real_time)),
+        );
+        b.data
+            .insert("command".to_string(), Value::String("<script>".to_string()));
+        b
+    }
+
+    #[test]
+    fn report_is_self_contained_and_escaped() {
+        let benchmarks = vec![benchmark("bubblesort", 2), benchmark("mergesort", 1)];
+
+        let html = build_report(
+            &benchmarks,
+            &[],
+            &["real_time".to_string()],
+            "algorithm",
+            None,
+            &HashMap::new(),
+            &DisplayOptions::default(),
+        )
+        .expect("should build report");
+
+        assert!(html.contains("<svg"), "should contain a chart");
+        assert!(html.contains("bubblesort"));
+        assert!(
+            !html.contains("\"<script>\""),
+            "values must not be able to inject HTML"
+        );
+        assert!(
+            !html.contains("<script src"),
+            "report should not load any assets"
+        );
+    }
+}
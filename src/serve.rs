@@ -0,0 +1,437 @@
+use crate::show::{
+    apply_filter, compute_2d_table_data, compute_key_infos, compute_table_data_1d, format_2d_rows,
+    group_1d_table_data, mixed_machines,
+};
+use crate::value::Aggregation;
+use crate::{BenchmarkRaw, Storage, Values};
+use anyhow::{anyhow, ensure, Context, Result};
+use itertools::Itertools;
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+use url::form_urlencoded;
+
+const INDEX_HTML: &str = include_str!("serve.html");
+const EXAMPLE_VALUES_DISPLAYED: usize = 5;
+
+/// time a client may take to send its request, so idle connections are closed eventually
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn json(body: serde_json::Value) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: json!({ "error": message }).to_string(),
+        }
+    }
+}
+
+/// serves the benchmarks of the current repository on localhost until the process is killed
+pub fn serve(storage: &Storage, port: u16) -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .with_context(|| format!("failed to listen on port {port}"))?;
+
+    println!("Serving benchmarks on http://localhost:{port} (press Ctrl-C to stop)");
+
+    for stream in listener.incoming() {
+        match stream {
+            // one thread per connection, so a slow client does not block the others
+            Ok(stream) => {
+                let storage = storage.clone();
+                thread::spawn(move || {
+                    if let Err(error) = handle_connection(stream, &storage, port) {
+                        eprintln!("failed to handle request: {:?}", error);
+                    }
+                });
+            }
+            Err(error) => eprintln!("failed to accept connection: {:?}", error),
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream, storage: &Storage, port: u16) -> Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // only the host is needed from the headers, no request needs a body
+    let mut host = None;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_string());
+            }
+        }
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        // other websites could read the benchmarks through a DNS name rebound to localhost
+        _ if !is_local_host(host.as_deref(), port) => {
+            Response::error(403, "only requests to localhost are allowed")
+        }
+        (Some("GET"), Some(target)) => {
+            let (path, query) = target.split_once('?').unwrap_or((target, ""));
+            let query: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect();
+
+            match storage.load_all_benchmarks() {
+                Ok(benchmarks) => route(path, &query, &benchmarks),
+                Err(error) => Response::error(500, &format!("{:#}", error)),
+            }
+        }
+        (Some(_), Some(_)) => Response::error(405, "only GET requests are supported"),
+        _ => Response::error(400, "malformed request"),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason_phrase(response.status),
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+
+    Ok(stream.flush()?)
+}
+
+/// whether the `Host` header names this server on localhost, without a port for port 80
+fn is_local_host(host: Option<&str>, port: u16) -> bool {
+    let host = match host {
+        Some(host) => host,
+        None => return false,
+    };
+    let (name, host_port) = match host.rsplit_once(':') {
+        Some((name, host_port)) => (name, host_port.parse().ok()),
+        None => (host, Some(80)),
+    };
+
+    host_port == Some(port) && (name == "localhost" || name == "127.0.0.1")
+}
+
+fn route(path: &str, query: &[(String, String)], benchmarks: &[BenchmarkRaw]) -> Response {
+    let result = parse_filter(query).and_then(|filter| {
+        let filtered = || benchmarks.iter().filter(|b| apply_filter(b, &filter));
+
+        Ok(match path {
+            "/" | "/index.html" => Response {
+                status: 200,
+                content_type: "text/html",
+                body: INDEX_HTML.to_string(),
+            },
+            "/api/benchmarks" => Response::json(json!(filtered().collect_vec())),
+            "/api/keys" => Response::json(keys(filtered())),
+            "/api/table" => Response::json(table(benchmarks, query, &filter)?),
+            "/api/series" => Response::json(series(benchmarks, query, &filter)?),
+            _ => Response::error(404, "not found"),
+        })
+    });
+
+    result.unwrap_or_else(|error| Response::error(400, &format!("{:#}", error)))
+}
+
+fn keys<'a>(benchmarks: impl DoubleEndedIterator<Item = &'a BenchmarkRaw>) -> serde_json::Value {
+    let infos = compute_key_infos(benchmarks, EXAMPLE_VALUES_DISPLAYED);
+
+    json!(infos
+        .iter()
+        .sorted_by(|a, b| a.0.cmp(b.0))
+        .map(|(key, info)| json!({
+            "key": key,
+            "occurrences": info.occurrences,
+            "example_values": info.example_values.iter().map(|v| v.to_string()).collect_vec(),
+        }))
+        .collect_vec())
+}
+
+/// a pivot table as in `show`, cells are aggregated if an aggregation is given
+fn table(
+    benchmarks: &[BenchmarkRaw],
+    query: &[(String, String)],
+    filter: &HashMap<String, String>,
+) -> Result<serde_json::Value> {
+    let row = required_param(query, "row")?;
+    let metric = required_param(query, "metric")?;
+    let aggregation = param(query, "aggregate")
+        .filter(|a| *a != "none")
+        .map(str::parse::<Aggregation>)
+        .transpose()?;
+
+    let display = |values: &Values| match aggregation {
+        Some(aggregation) => values
+            .aggregate(aggregation)
+            .map_or_else(|| values.to_string(), |v| v.to_string()),
+        None => values.to_string(),
+    };
+
+    let grouped_by = [Some(row), param(query, "col")];
+    let warning = check_machines(benchmarks, query, filter, &grouped_by)?;
+
+    Ok(match param(query, "col") {
+        Some(col) => {
+            let data = compute_2d_table_data(benchmarks, row, col, metric, filter);
+
+            let mut headers = data.table_headers.clone();
+            headers[0] = row.to_string();
+
+            json!({ "headers": headers, "rows": format_2d_rows(&data, display), "warning": warning })
+        }
+        None => {
+            let groups =
+                group_1d_table_data(compute_table_data_1d(benchmarks, row, metric, filter));
+
+            let rows = groups
+                .iter()
+                .map(|(label, values)| vec![label.clone(), display(values)])
+                .collect_vec();
+
+            json!({ "headers": [row, metric], "rows": rows, "warning": warning })
+        }
+    })
+}
+
+/// numeric data points of a metric by row, used to draw charts
+fn series(
+    benchmarks: &[BenchmarkRaw],
+    query: &[(String, String)],
+    filter: &HashMap<String, String>,
+) -> Result<serde_json::Value> {
+    let row = required_param(query, "row")?;
+    let metric = required_param(query, "metric")?;
+    let aggregation = param(query, "aggregate")
+        .filter(|a| *a != "none")
+        .map_or(Ok(Aggregation::Mean), str::parse)?;
+
+    // the warning is shown with the table
+    check_machines(benchmarks, query, filter, &[Some(row)])?;
+
+    let groups = group_1d_table_data(compute_table_data_1d(benchmarks, row, metric, filter));
+
+    Ok(json!(groups
+        .iter()
+        .filter_map(|(label, values)| {
+            let value = values.aggregate(aggregation)?;
+
+            Some(json!({
+                "label": label,
+                "value": value.as_f64()?,
+                "display": value.to_string(),
+                "samples": values.0.len(),
+            }))
+        })
+        .collect_vec()))
+}
+
+/// fails if benchmarks of different machines are compared, unless `all_machines` is given, then
+/// the warning is returned to be shown with the result
+fn check_machines(
+    benchmarks: &[BenchmarkRaw],
+    query: &[(String, String)],
+    filter: &HashMap<String, String>,
+    grouped_by: &[Option<&str>],
+) -> Result<Option<String>> {
+    let grouped_by = grouped_by.iter().flatten().copied().collect_vec();
+    let message = match mixed_machines(benchmarks, filter, &grouped_by) {
+        Some(message) => message,
+        None => return Ok(None),
+    };
+
+    ensure!(
+        param(query, "all_machines").is_some(),
+        "{message}, filter by one with machine_id=<ID>, use machine_id as row or column or select all machines to compare them anyway"
+    );
+
+    Ok(Some(message))
+}
+
+fn parse_filter(query: &[(String, String)]) -> Result<HashMap<String, String>> {
+    query
+        .iter()
+        .filter(|(key, _)| key == "filter")
+        .map(|(_, predicate)| {
+            predicate
+                .split_once('=')
+                .filter(|(k, v)| !k.is_empty() && !v.is_empty())
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .ok_or_else(|| anyhow!("filter has to be a <key>=<value> pair: {predicate}"))
+        })
+        .collect()
+}
+
+fn param<'a>(query: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    query
+        .iter()
+        .find(|(key, value)| key == name && !value.is_empty())
+        .map(|(_, value)| value.as_str())
+}
+
+fn required_param<'a>(query: &'a [(String, String)], name: &str) -> Result<&'a str> {
+    param(query, name).ok_or_else(|| anyhow!("missing query parameter \"{name}\""))
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Value;
+
+    fn benchmarks() -> Vec<BenchmarkRaw> {
+        [
+            ("bubblesort", 10, 4),
+            ("bubblesort", 10, 2),
+            ("mergesort", 100, 1),
+        ]
+        .iter()
+        .map(|(algorithm, elements, time)| {
+            let mut b = BenchmarkRaw::default();
+            b.data.insert(
+                "algorithm".to_string(),
+                Value::String(algorithm.to_string()),
+            );
+            b.data
+                .insert("elements".to_string(), Value::Integer(*elements));
+            b.data.insert("time".to_string(), Value::Integer(*time));
+            b
+        })
+        .collect()
+    }
+
+    fn query(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn benchmarks_can_be_filtered() {
+        let response = route(
+            "/api/benchmarks",
+            &query(&[("filter", "algorithm=mergesort")]),
+            &benchmarks(),
+        );
+
+        assert_eq!(response.status, 200);
+        let body: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(body.as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn table_is_pivoted_and_aggregated() {
+        let response = route(
+            "/api/table",
+            &query(&[
+                ("row", "algorithm"),
+                ("col", "elements"),
+                ("metric", "time"),
+                ("aggregate", "mean"),
+            ]),
+            &benchmarks(),
+        );
+
+        assert_eq!(response.status, 200);
+        let body: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(body["headers"], json!(["algorithm", "10", "100"]));
+        assert_eq!(body["rows"][0], json!(["bubblesort", "3", ""]));
+    }
+
+    #[test]
+    fn benchmarks_of_different_machines_are_only_compared_on_request() {
+        let mut benchmarks = benchmarks();
+        for (i, b) in benchmarks.iter_mut().enumerate() {
+            b.data.insert(
+                "machine_id".to_string(),
+                Value::String(format!("m{}", i % 2)),
+            );
+        }
+        let table = |extra: &[(&str, &str)]| {
+            let mut pairs = vec![("row", "algorithm"), ("metric", "time")];
+            pairs.extend(extra);
+            route("/api/table", &query(&pairs), &benchmarks)
+        };
+
+        assert_eq!(table(&[]).status, 400);
+        assert_eq!(
+            route(
+                "/api/series",
+                &query(&[("row", "algorithm"), ("metric", "time")]),
+                &benchmarks
+            )
+            .status,
+            400
+        );
+        assert_eq!(table(&[("col", "machine_id")]).status, 200);
+        assert_eq!(table(&[("filter", "machine_id=m0")]).status, 200);
+
+        let response = table(&[("all_machines", "true")]);
+        assert_eq!(response.status, 200);
+        let body: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(
+            body["warning"],
+            json!("benchmarks of 2 machines are compared (m0, m1)")
+        );
+    }
+
+    #[test]
+    fn only_requests_to_localhost_are_allowed() {
+        assert!(is_local_host(Some("localhost:8080"), 8080));
+        assert!(is_local_host(Some("127.0.0.1:8080"), 8080));
+        assert!(is_local_host(Some("localhost"), 80));
+        assert!(!is_local_host(Some("localhost"), 8080));
+        assert!(!is_local_host(Some("localhost:8081"), 8080));
+        assert!(!is_local_host(Some("attacker.example:8080"), 8080));
+        assert!(!is_local_host(None, 8080));
+    }
+
+    #[test]
+    fn missing_parameters_and_unknown_paths_are_reported() {
+        assert_eq!(route("/api/table", &[], &benchmarks()).status, 400);
+        assert_eq!(route("/unknown", &[], &benchmarks()).status, 404);
+        assert_eq!(
+            route(
+                "/api/keys",
+                &query(&[("filter", "nokeyvalue")]),
+                &benchmarks()
+            )
+            .status,
+            400
+        );
+    }
+}
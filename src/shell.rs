@@ -0,0 +1,171 @@
+use crate::benchmark::{ExecutionOptions, ExecutionResult};
+use crate::os::execute_and_measure;
+use crate::value::{self, Aggregation, Value, Values};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// how often the empty command is run to calibrate the overhead of the shell
+const CALIBRATION_RUNS: usize = 10;
+
+/// a shell which runs the command, to benchmark pipelines and redirections
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Sh,
+    Bash,
+    Zsh,
+}
+
+impl Shell {
+    pub const VARIANTS: &'static [&'static str] = &["sh", "bash", "zsh"];
+
+    /// the command line to run the command with this shell, the command is passed on unchanged
+    /// as a single string, so quoting and splitting it is up to the shell
+    pub(crate) fn wrap(&self, command: &str) -> Vec<String> {
+        vec![self.to_string(), "-c".to_string(), command.to_string()]
+    }
+}
+
+impl FromStr for Shell {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sh" => Ok(Shell::Sh),
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            _ => Err(anyhow!(
+                "unknown shell \"{}\", expected one of: {}",
+                s,
+                Shell::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Shell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Shell::Sh => "sh",
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// the median times of the shell running an empty command
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ShellOverhead {
+    pub user_time: Duration,
+    pub system_time: Duration,
+    pub real_time: Duration,
+}
+
+impl ShellOverhead {
+    /// runs the empty command with the shell a few times, with the same options as the benchmark
+    pub(crate) fn calibrate(shell: Shell, options: &ExecutionOptions) -> Result<Self> {
+        let options = ExecutionOptions {
+            quiet: true,
+            capture_stderr: false,
+            input: None,
+            counters: false,
+            sample_interval: None,
+            cgroup: false,
+            ..options.clone()
+        };
+
+        let mut results = vec![];
+        for _ in 0..CALIBRATION_RUNS {
+            let (result, _, _) = execute_and_measure(&shell.wrap(""), &options)
+                .with_context(|| format!("failed to calibrate overhead of {shell}"))?;
+            results.push(result);
+        }
+
+        let median = |time: fn(&ExecutionResult) -> Duration| {
+            let values = Values(results.iter().map(|r| Value::Duration(time(r))).collect());
+
+            match values.aggregate(Aggregation::Median) {
+                Some(Value::Duration(median)) => median,
+                _ => Duration::ZERO,
+            }
+        };
+
+        Ok(Self {
+            user_time: median(|r| r.user_time),
+            system_time: median(|r| r.system_time),
+            real_time: median(|r| r.real_time),
+        })
+    }
+
+    /// subtracts the overhead from the result and returns the uncorrected times
+    pub(crate) fn correct(&self, result: &mut ExecutionResult) -> ShellSummary {
+        let raw = ShellSummary {
+            raw_user_time: Some(result.user_time),
+            raw_system_time: Some(result.system_time),
+            raw_real_time: Some(result.real_time),
+            ..Default::default()
+        };
+
+        result.user_time = result.user_time.saturating_sub(self.user_time);
+        result.system_time = result.system_time.saturating_sub(self.system_time);
+        result.real_time = result.real_time.saturating_sub(self.real_time);
+
+        raw
+    }
+}
+
+/// the shell of a run and, if corrected, its times before the correction
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellSummary {
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub raw_user_time: Option<Duration>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub raw_system_time: Option<Duration>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub raw_real_time: Option<Duration>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn command_is_wrapped_into_one_argument() {
+        assert_eq!(
+            Shell::Bash.wrap("cat 'my data' | wc -l"),
+            vec!["bash", "-c", "cat 'my data' | wc -l"]
+        );
+        assert!("fish".parse::<Shell>().is_err());
+    }
+
+    #[test]
+    fn overhead_is_subtracted_without_underflow() {
+        let overhead = ShellOverhead {
+            user_time: Duration::from_millis(2),
+            system_time: Duration::from_millis(1),
+            real_time: Duration::from_millis(5),
+        };
+        let mut result = ExecutionResult {
+            user_time: Duration::from_millis(10),
+            system_time: Duration::ZERO,
+            real_time: Duration::from_millis(20),
+            ..Default::default()
+        };
+
+        let raw = overhead.correct(&mut result);
+
+        assert_eq!(result.user_time, Duration::from_millis(8));
+        assert_eq!(result.system_time, Duration::ZERO);
+        assert_eq!(result.real_time, Duration::from_millis(15));
+        assert_eq!(raw.raw_real_time, Some(Duration::from_millis(20)));
+    }
+}
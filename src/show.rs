@@ -1,12 +1,16 @@
-use crate::{load_all_benchmarks, BenchmarkRaw, Value, Values};
-use anyhow::Result;
+use crate::chart::{bar_chart, box_plot, line_chart, terminal_width, Chart};
+use crate::value::{Aggregation, DisplayOptions};
+use crate::{BenchmarkRaw, Storage, Value, Values};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
 use cli_table::{format::Justify, Cell, Style, Table, TableStruct};
+use colored::*;
 use itertools::Itertools;
 use std::collections::HashMap;
 
-pub fn show(filter: &HashMap<String, String>) -> Result<()> {
+pub fn show(storage: &Storage, filter: &HashMap<String, String>) -> Result<()> {
     const EXAMPLE_VALUES_DISPLAYED: usize = 3;
-    let benchmarks = load_all_benchmarks()?;
+    let benchmarks = storage.load_all_benchmarks()?;
     let key_infos = compute_key_infos(
         benchmarks.iter().filter(|b| apply_filter(b, filter)),
         EXAMPLE_VALUES_DISPLAYED,
@@ -14,7 +18,7 @@ pub fn show(filter: &HashMap<String, String>) -> Result<()> {
 
     let rows: Vec<_> = key_infos
         .iter()
-        .sorted_by(|a, b| (*a).0.cmp((*b).0))
+        .sorted_by(|a, b| a.0.cmp(b.0))
         .map(|(key, info)| {
             vec![
                 key.cell(),
@@ -46,28 +50,31 @@ pub fn show(filter: &HashMap<String, String>) -> Result<()> {
 fn display_example_values(values: &[Value]) -> String {
     match values.len() {
         0 => String::from(""),
-        1 => format!("{}", values.get(0).expect("checked")),
+        1 => format!("{}", values.first().expect("checked")),
         2 => format!(
             "{}, {}",
-            values.get(0).expect("checked"),
+            values.first().expect("checked"),
             values.get(1).expect("checked")
         ),
         _ => format!(
             "{}, {}, {},...",
-            values.get(0).expect("checked"),
+            values.first().expect("checked"),
             values.get(1).expect("checked"),
             values.get(2).expect("checked")
         ),
     }
 }
 
-struct KeyInfo {
-    occurrences: u64,
-    example_values: Vec<Value>,
+pub(crate) struct KeyInfo {
+    pub(crate) occurrences: u64,
+    pub(crate) example_values: Vec<Value>,
     example_values_str_len: usize,
 }
 
-fn compute_key_infos<'a, It>(benchmarks: It, max_example_values: usize) -> HashMap<String, KeyInfo>
+pub(crate) fn compute_key_infos<'a, It>(
+    benchmarks: It,
+    max_example_values: usize,
+) -> HashMap<String, KeyInfo>
 where
     It: IntoIterator<Item = &'a BenchmarkRaw>,
     <It as IntoIterator>::IntoIter: DoubleEndedIterator,
@@ -108,16 +115,71 @@ where
     info_per_key
 }
 
-fn apply_filter(benchmark: &BenchmarkRaw, filter: &HashMap<String, String>) -> bool {
+pub(crate) fn apply_filter(benchmark: &BenchmarkRaw, filter: &HashMap<String, String>) -> bool {
     filter.iter().all(|(key, value)| {
         benchmark
             .data
             .get(key)
-            .map_or(false, |other| &other.to_string() == value)
+            .is_some_and(|other| &other.to_string() == value)
     })
 }
 
-type Row1d = (String, String);
+/// fails if the filtered benchmarks were recorded on different machines, as their metrics are
+/// not comparable, unless they are grouped by machine or `all_machines` is set, which only warns
+pub(crate) fn check_machines(
+    benchmarks: &[BenchmarkRaw],
+    filter: &HashMap<String, String>,
+    grouped_by: &[&str],
+    all_machines: bool,
+) -> Result<()> {
+    let message = match mixed_machines(benchmarks, filter, grouped_by) {
+        Some(message) => message,
+        None => return Ok(()),
+    };
+
+    if !all_machines {
+        bail!("{message}, filter by one with --filter machine_id=<ID>, group by machine_id or pass --all-machines to compare them anyway");
+    }
+
+    println!("{}", format!("warning: {message}").yellow());
+
+    Ok(())
+}
+
+/// describes the machines if the filtered benchmarks were recorded on different ones and are
+/// not grouped by machine
+pub(crate) fn mixed_machines(
+    benchmarks: &[BenchmarkRaw],
+    filter: &HashMap<String, String>,
+    grouped_by: &[&str],
+) -> Option<String> {
+    if grouped_by
+        .iter()
+        .any(|key| matches!(*key, "machine_id" | "machine_label" | "hostname"))
+    {
+        return None;
+    }
+
+    // benchmarks recorded before machine IDs existed are assumed to be comparable
+    let machines: Vec<_> = benchmarks
+        .iter()
+        .filter(|b| apply_filter(b, filter))
+        .filter_map(|b| b.data.get("machine_id"))
+        .map(ToString::to_string)
+        .unique()
+        .sorted()
+        .collect();
+
+    (machines.len() > 1).then(|| {
+        format!(
+            "benchmarks of {} machines are compared ({})",
+            machines.len(),
+            machines.join(", ")
+        )
+    })
+}
+
+pub(crate) type Row1d = (String, Value);
 
 fn benchmark_to_row(row: &str, metric: &str, benchmark: &BenchmarkRaw) -> Option<Row1d> {
     let row_value = benchmark.data.get(row);
@@ -126,7 +188,7 @@ fn benchmark_to_row(row: &str, metric: &str, benchmark: &BenchmarkRaw) -> Option
     match (row_value, metric_value) {
         (Some(row_value), Some(metric_value)) => {
             let row_value = format!("{}", row_value);
-            let metric_value = format!("{}", metric_value);
+            let metric_value = metric_value.clone();
 
             // add row to table
             Some((row_value, metric_value))
@@ -135,13 +197,32 @@ fn benchmark_to_row(row: &str, metric: &str, benchmark: &BenchmarkRaw) -> Option
     }
 }
 
-pub fn show_1d_table(row: &str, metric: &str, filter: &HashMap<String, String>) -> Result<()> {
-    let benchmarks = load_all_benchmarks()?;
-
-    let TableData1d {
-        rows,
-        empty_matches,
-    } = compute_table_data_1d(&benchmarks, row, metric, filter);
+pub fn show_1d_table(
+    storage: &Storage,
+    row: &str,
+    metric: &str,
+    filter: &HashMap<String, String>,
+    display: &DisplayOptions,
+    all_machines: bool,
+) -> Result<()> {
+    let benchmarks = storage.load_all_benchmarks()?;
+    check_machines(&benchmarks, filter, &[row], all_machines)?;
+
+    let data = compute_table_data_1d(&benchmarks, row, metric, filter);
+    let empty_matches = data.empty_matches;
+
+    // repeated runs are only merged into one row if they are aggregated
+    let rows = if display.aggregation.is_some() {
+        group_1d_table_data(data)
+            .iter()
+            .map(|(label, values)| (label.clone(), display.format_values(values)))
+            .collect_vec()
+    } else {
+        data.rows
+            .iter()
+            .map(|(label, value)| (label.clone(), display.format_value(value)))
+            .collect_vec()
+    };
 
     println!("Showing 1-dimensional table with:");
     println!("row: {}, metric: {}\n", row, metric);
@@ -161,12 +242,12 @@ pub fn show_1d_table(row: &str, metric: &str, filter: &HashMap<String, String>)
     Ok(())
 }
 
-struct TableData1d {
-    rows: Vec<Row1d>,
-    empty_matches: usize,
+pub(crate) struct TableData1d {
+    pub(crate) rows: Vec<Row1d>,
+    pub(crate) empty_matches: usize,
 }
 
-fn compute_table_data_1d(
+pub(crate) fn compute_table_data_1d(
     benchmarks: &[BenchmarkRaw],
     row: &str,
     metric: &str,
@@ -187,7 +268,7 @@ fn compute_table_data_1d(
     }
 }
 
-fn build_1d_table(col1_title: &str, col2_title: &str, rows: &[Row1d]) -> TableStruct {
+fn build_1d_table(col1_title: &str, col2_title: &str, rows: &[(String, String)]) -> TableStruct {
     rows.iter()
         .map(|(row, metric)| vec![row.cell(), metric.cell().justify(Justify::Right)])
         .table()
@@ -199,12 +280,16 @@ fn build_1d_table(col1_title: &str, col2_title: &str, rows: &[Row1d]) -> TableSt
 }
 
 pub fn show_2d_table(
+    storage: &Storage,
     row: &str,
     col: &str,
     metric: &str,
     filter: &HashMap<String, String>,
+    display: &DisplayOptions,
+    all_machines: bool,
 ) -> Result<()> {
-    let benchmarks = load_all_benchmarks()?;
+    let benchmarks = storage.load_all_benchmarks()?;
+    check_machines(&benchmarks, filter, &[row, col], all_machines)?;
 
     let data = compute_2d_table_data(&benchmarks, row, col, metric, filter);
 
@@ -214,16 +299,16 @@ pub fn show_2d_table(
     if data.matrix.is_empty() {
         println!("Result is empty");
     } else {
-        println!("{}", build_2d_table(&data).display()?);
+        println!("{}", build_2d_table(&data, display).display()?);
     }
 
     Ok(())
 }
 
-fn build_2d_table(data: &TableData2d) -> TableStruct {
+fn build_2d_table(data: &TableData2d, display: &DisplayOptions) -> TableStruct {
     data.matrix
         .iter()
-        .sorted_by(|a, b| (*a).0.cmp((*b).0))
+        .sorted_by(|a, b| a.0.cmp(b.0))
         .map(|(row, col_to_metrics)| {
             let mut table_row = vec![row.clone().cell()];
             for _ in 1..data.table_headers.len() {
@@ -231,9 +316,7 @@ fn build_2d_table(data: &TableData2d) -> TableStruct {
             }
 
             for (col, metrics) in col_to_metrics.iter() {
-                // TODO: here we can aggregate 'metrics'
-                let metric_value = format!("{}", metrics);
-                table_row[data.col_to_pos[col]] = metric_value.cell();
+                table_row[data.col_to_pos[col]] = display.format_values(metrics).cell();
             }
 
             table_row
@@ -242,13 +325,183 @@ fn build_2d_table(data: &TableData2d) -> TableStruct {
         .title(&data.table_headers)
 }
 
-struct TableData2d {
-    table_headers: Vec<String>,
-    col_to_pos: HashMap<String, usize>,
-    matrix: HashMap<String, HashMap<String, Values>>,
+/// formats the matrix of a 2d table row by row, sorted by the row value, which is the first cell
+pub(crate) fn format_2d_rows(
+    data: &TableData2d,
+    format: impl Fn(&Values) -> String,
+) -> Vec<Vec<String>> {
+    data.matrix
+        .iter()
+        .sorted_by(|a, b| a.0.cmp(b.0))
+        .map(|(row, col_to_metrics)| {
+            let mut cells = vec![String::new(); data.table_headers.len()];
+            cells[0] = row.clone();
+
+            for (col, metrics) in col_to_metrics.iter() {
+                cells[data.col_to_pos[col]] = format(metrics);
+            }
+
+            cells
+        })
+        .collect()
 }
 
-fn compute_2d_table_data(
+#[allow(clippy::too_many_arguments)]
+pub fn show_chart(
+    storage: &Storage,
+    chart: Chart,
+    row: &str,
+    col: Option<&str>,
+    metric: &str,
+    filter: &HashMap<String, String>,
+    display: &DisplayOptions,
+    all_machines: bool,
+) -> Result<()> {
+    let benchmarks = storage.load_all_benchmarks()?;
+    let grouped_by: Vec<_> = std::iter::once(row).chain(col).collect();
+    check_machines(&benchmarks, filter, &grouped_by, all_machines)?;
+
+    let mut groups = match col {
+        Some(col) => {
+            let data = compute_2d_table_data(&benchmarks, row, col, metric, filter);
+            group_2d_table_data(data)
+        }
+        None => group_1d_table_data(compute_table_data_1d(&benchmarks, row, metric, filter)),
+    };
+    if chart == Chart::Line && row == "commit_id" {
+        order_by_first_benchmark(&mut groups, &benchmarks, row, filter);
+    }
+
+    println!("Showing {} chart with:", chart);
+    match col {
+        Some(col) => println!("row: {}, col: {}, metric: {}\n", row, col, metric),
+        None => println!("row: {}, metric: {}\n", row, metric),
+    }
+
+    if groups.is_empty() {
+        println!("Result is empty");
+        return Ok(());
+    }
+
+    if groups
+        .iter()
+        .flat_map(|(_, values)| values.0.iter())
+        .any(|v| v.as_f64().is_none())
+    {
+        bail!("\"{metric}\" is not a numeric metric and can not be charted");
+    }
+
+    let width = terminal_width();
+
+    let output = match chart {
+        Chart::Bar | Chart::Line => {
+            let entries: Vec<_> = groups
+                .into_iter()
+                .filter_map(|(label, values)| {
+                    values
+                        .aggregate(display.aggregation.unwrap_or(Aggregation::Mean))
+                        .map(|value| (label, value))
+                })
+                .collect();
+
+            if chart == Chart::Bar {
+                bar_chart(&entries, width, display)
+            } else {
+                line_chart(&entries, width, display)
+            }
+        }
+        Chart::Box => {
+            let entries: Vec<_> = groups
+                .into_iter()
+                .map(|(label, values)| (label, values.0))
+                .collect();
+
+            box_plot(&entries, width, display)
+        }
+    };
+
+    println!("{}", output);
+
+    Ok(())
+}
+
+/// commit ids sort arbitrarily, so their groups are ordered by the time their row value was
+/// first benchmarked, keeping the order of the columns of a row
+fn order_by_first_benchmark(
+    groups: &mut [(String, Values)],
+    benchmarks: &[BenchmarkRaw],
+    row: &str,
+    filter: &HashMap<String, String>,
+) {
+    let mut first_benchmarked: HashMap<String, DateTime<Utc>> = HashMap::new();
+    for benchmark in benchmarks.iter().filter(|b| apply_filter(b, filter)) {
+        if let (Some(value), Some(Value::Timestamp(created_at))) =
+            (benchmark.data.get(row), benchmark.data.get("created_at"))
+        {
+            first_benchmarked
+                .entry(value.to_string())
+                .and_modify(|first| *first = (*first).min(*created_at))
+                .or_insert(*created_at);
+        }
+    }
+
+    groups.sort_by_key(|(label, _)| {
+        let row_value = label
+            .split_once(" / ")
+            .map_or(label.as_str(), |(row, _)| row);
+        first_benchmarked.get(row_value).copied()
+    });
+}
+
+/// groups the metrics of all rows with the same row value, ordered numerically if all row
+/// values are numbers, otherwise ordered alphabetically
+pub(crate) fn group_1d_table_data(data: TableData1d) -> Vec<(String, Values)> {
+    let mut groups: Vec<(String, Values)> = vec![];
+
+    for (row, metric) in data.rows {
+        match groups.last_mut() {
+            Some((label, values)) if *label == row => values.push(metric),
+            _ => groups.push((row, Values(vec![metric]))),
+        }
+    }
+
+    if groups.iter().all(|(label, _)| label.parse::<f64>().is_ok()) {
+        groups.sort_by(|(l, _), (r, _)| {
+            let l = l.parse::<f64>().expect("checked");
+            let r = r.parse::<f64>().expect("checked");
+            l.total_cmp(&r)
+        });
+    }
+
+    groups
+}
+
+/// flattens the 2d matrix to one group per cell, ordered by row and column
+pub(crate) fn group_2d_table_data(data: TableData2d) -> Vec<(String, Values)> {
+    let TableData2d {
+        col_to_pos, matrix, ..
+    } = data;
+
+    matrix
+        .into_iter()
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+        .flat_map(|(row, col_to_metrics)| {
+            col_to_metrics
+                .into_iter()
+                .sorted_by_key(|(col, _)| col_to_pos[col])
+                .map(move |(col, values)| (format!("{} / {}", row, col), values))
+                .collect_vec()
+        })
+        .collect()
+}
+
+pub(crate) struct TableData2d {
+    pub(crate) table_headers: Vec<String>,
+    pub(crate) col_to_pos: HashMap<String, usize>,
+    pub(crate) matrix: HashMap<String, HashMap<String, Values>>,
+}
+
+pub(crate) fn compute_2d_table_data(
     benchmarks: &[BenchmarkRaw],
     row: &str,
     col: &str,
@@ -356,7 +609,7 @@ mod test {
         let i = infos.get("command").expect("should work");
 
         assert_eq!(
-            i.example_values.get(0),
+            i.example_values.first(),
             Some(&Value::String("hello".to_string())),
             "hello should come first because it is saved in the newer benchmark"
         );
@@ -385,6 +638,25 @@ mod test {
         );
     }
 
+    #[test]
+    fn chart_groups_of_numeric_rows_are_ordered_numerically() {
+        let data = TableData1d {
+            rows: vec![
+                ("10".to_string(), Value::Integer(1)),
+                ("100".to_string(), Value::Integer(2)),
+                ("100".to_string(), Value::Integer(3)),
+                ("20".to_string(), Value::Integer(4)),
+            ],
+            empty_matches: 0,
+        };
+
+        let groups = group_1d_table_data(data);
+
+        let labels: Vec<_> = groups.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, vec!["10", "20", "100"]);
+        assert_eq!(groups[2].1 .0.len(), 2, "same row values are grouped");
+    }
+
     #[test]
     fn remove_benchmark_with_missing_key_in_filter() {
         let benchmark = BenchmarkRaw::default();
@@ -412,6 +684,23 @@ mod test {
         );
     }
 
+    #[test]
+    fn mixing_machines_is_refused_unless_grouped_or_allowed() {
+        let on = |machine: &str| BenchmarkRaw {
+            data: HashMap::from([("machine_id".to_string(), Value::String(machine.to_string()))]),
+        };
+        let benchmarks = vec![on("laptop"), on("server"), BenchmarkRaw::default()];
+        let no_filter = HashMap::new();
+
+        assert!(check_machines(&benchmarks, &no_filter, &["command"], false).is_err());
+        assert!(check_machines(&benchmarks, &no_filter, &["command"], true).is_ok());
+        assert!(check_machines(&benchmarks, &no_filter, &["machine_id"], false).is_ok());
+
+        let one_machine = HashMap::from([("machine_id".to_string(), "server".to_string())]);
+        assert!(check_machines(&benchmarks, &one_machine, &["command"], false).is_ok());
+        assert!(check_machines(&benchmarks[2..], &no_filter, &[], false).is_ok());
+    }
+
     #[test]
     fn benchmark_should_not_get_filtered_out_if_all_filters_do_match() {
         let filter = HashMap::from([
@@ -431,4 +720,39 @@ mod test {
             "benchmark should pass the filter if all key value pairs match the filter"
         );
     }
+
+    #[test]
+    fn commits_are_ordered_by_their_first_benchmark() {
+        let benchmark = |commit_id: &str, created_at: &str| {
+            let mut b = BenchmarkRaw::default();
+            b.data.insert(
+                "commit_id".to_string(),
+                Value::String(commit_id.to_string()),
+            );
+            b.data.insert(
+                "created_at".to_string(),
+                Value::Timestamp(created_at.parse().unwrap()),
+            );
+            b.data.insert("real_time".to_string(), Value::Float(1.0));
+            b
+        };
+        let benchmarks = [
+            benchmark("aaa", "2022-05-14T20:00:00Z"),
+            benchmark("fff", "2022-05-12T20:00:00Z"),
+            benchmark("ccc", "2022-05-13T20:00:00Z"),
+            benchmark("aaa", "2022-05-11T20:00:00Z"),
+        ];
+        let filter = HashMap::new();
+        let mut groups = group_1d_table_data(compute_table_data_1d(
+            &benchmarks,
+            "commit_id",
+            "real_time",
+            &filter,
+        ));
+
+        order_by_first_benchmark(&mut groups, &benchmarks, "commit_id", &filter);
+
+        let labels: Vec<_> = groups.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, vec!["aaa", "fff", "ccc"]);
+    }
 }
@@ -1,4 +1,6 @@
 use crate::benchmark::{Benchmark, BenchmarkRaw};
+use crate::config::{Config, StorageConfig, StorageFormat};
+use crate::output::CommandOutput;
 use crate::read_git_info;
 use anyhow::{Context, Result};
 use serde::de::DeserializeOwned;
@@ -13,60 +15,113 @@ pub struct Data<T> {
     benchmarks: Vec<T>,
 }
 
-/// loads benchmarks from the file in order from oldest to newest.
-pub fn load_all_benchmarks() -> Result<Vec<BenchmarkRaw>> {
-    read_from_storage::<Data<BenchmarkRaw>>().map(|d| d.benchmarks)
+/// location and format of the data file, resolved once from the configuration, so all runs of
+/// an invocation use the same file even if the configuration changes meanwhile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Storage {
+    dir: PathBuf,
+    format: StorageFormat,
 }
 
-pub fn append_benchmark(benchmark: &Benchmark) -> Result<()> {
-    let mut data = read_from_storage::<Data<Benchmark>>()?;
+impl Default for Storage {
+    fn default() -> Self {
+        Self::new(&StorageConfig::default())
+    }
+}
 
-    data.benchmarks.push(benchmark.clone());
+impl Storage {
+    /// resolves the data directory relative to the root of the current project
+    pub fn new(config: &StorageConfig) -> Self {
+        const PATH: &str = ".benchie";
 
-    write_to_storage(&data)?;
+        Self {
+            dir: project_dir_path().join(config.path.as_deref().unwrap_or_else(|| Path::new(PATH))),
+            format: config.format,
+        }
+    }
 
-    Ok(())
-}
+    /// loads benchmarks from the file in order from oldest to newest.
+    pub fn load_all_benchmarks(&self) -> Result<Vec<BenchmarkRaw>> {
+        self.read::<Data<BenchmarkRaw>>().map(|d| d.benchmarks)
+    }
 
-fn data_dir_path() -> PathBuf {
-    const PATH: &str = ".benchie";
+    pub fn append_benchmark(&self, benchmark: &Benchmark) -> Result<()> {
+        // stored benchmarks are kept as they are, reading them as `Benchmark` would duplicate the
+        // fields of nested flattened structs into the flattened tags
+        let mut data = self.read::<Data<BenchmarkRaw>>()?;
 
-    if let Ok(git) = read_git_info() {
-        git.path.join(PATH)
-    } else {
-        Path::new(PATH).into()
+        data.benchmarks
+            .push(serde_json::from_value(serde_json::to_value(benchmark)?)?);
+
+        self.write(&data)?;
+
+        Ok(())
     }
-}
 
-fn data_file_path() -> PathBuf {
-    data_dir_path().join("data.json")
-}
+    /// writes stdout and, if captured, stderr of a run to `outputs/<id>` in the data directory
+    pub(crate) fn write_output(&self, id: &str, output: &CommandOutput) -> Result<PathBuf> {
+        let dir_path = self.dir.join("outputs").join(id);
+        fs::create_dir_all(&dir_path)?;
 
-fn read_from_storage<T>() -> Result<T>
-where
-    T: DeserializeOwned,
-{
-    let default = serde_json::to_string(&json! {
-       {
-           "schema": 1,
-           "benchmarks": []
-       }
-    })?;
+        fs::write(dir_path.join("stdout"), &output.stdout)?;
+        if let Some(stderr) = &output.stderr {
+            fs::write(dir_path.join("stderr"), stderr)?;
+        }
 
-    let raw = fs::read_to_string(data_file_path()).unwrap_or(default);
+        Ok(dir_path)
+    }
 
-    serde_json::from_str::<T>(&raw).context("failed to parse benchie data file")
-}
+    fn data_file_path(&self) -> PathBuf {
+        self.dir.join("data.json")
+    }
 
-fn write_to_storage<T: Serialize>(data: &Data<T>) -> Result<()> {
-    // serialize benchmark to a JSON string
-    let json = serde_json::to_string(data)?;
+    fn read<T>(&self) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let default = serde_json::to_string(&json! {
+           {
+               "schema": 1,
+               "benchmarks": []
+           }
+        })?;
 
-    let dir_path = data_dir_path();
-    if !dir_path.exists() {
-        fs::create_dir(&dir_path)?;
+        let raw = fs::read_to_string(self.data_file_path()).unwrap_or(default);
+
+        serde_json::from_str::<T>(&raw).context("failed to parse benchie data file")
+    }
+
+    fn write<T: Serialize>(&self, data: &Data<T>) -> Result<()> {
+        // serialize benchmark to a JSON string
+        let json = match self.format {
+            StorageFormat::Json => serde_json::to_string(data)?,
+            StorageFormat::PrettyJson => serde_json::to_string_pretty(data)?,
+        };
+
+        if !self.dir.exists() {
+            fs::create_dir_all(&self.dir)?;
+        }
+        fs::write(self.data_file_path(), json)?;
+
+        Ok(())
     }
-    fs::write(data_file_path(), json)?;
+}
 
-    Ok(())
+/// loads the benchmarks of the current project, see [`Storage::load_all_benchmarks`]
+pub fn load_all_benchmarks() -> Result<Vec<BenchmarkRaw>> {
+    Storage::new(&Config::load()?.storage).load_all_benchmarks()
+}
+
+/// appends a benchmark to the data file of the current project
+pub fn append_benchmark(benchmark: &Benchmark) -> Result<()> {
+    Storage::new(&Config::load()?.storage).append_benchmark(benchmark)
+}
+
+/// the root of the Git repository or the current directory if there is no repository
+pub(crate) fn project_dir_path() -> PathBuf {
+    if let Ok(git) = read_git_info() {
+        git.path
+    } else {
+        Path::new(".").into()
+    }
 }
@@ -0,0 +1,253 @@
+use crate::benchmark::{benchmark, BenchmarkOptions, ExecutionOptions};
+use crate::config::Config;
+use crate::storage::{project_dir_path, Storage};
+use crate::utils::parse_duration;
+use anyhow::{bail, ensure, Context, Result};
+use itertools::Itertools;
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const SUITES_FILE: &str = "benchie-suites.toml";
+
+/// one benchmark of a suite, every run of it is stored as normal benchmark
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SuiteEntry {
+    command: Vec<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+    /// working directory, relative to the directory of the suite file
+    cwd: Option<PathBuf>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    #[serde(default)]
+    env_clear: bool,
+    #[serde(default)]
+    env_keep: Vec<String>,
+    setup: Option<String>,
+    prepare: Option<String>,
+    cleanup: Option<String>,
+    teardown: Option<String>,
+    runs: Option<usize>,
+    /// e.g. "30s", runs exceeding it are killed and marked as timed out
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    timeout: Option<Duration>,
+}
+
+fn deserialize_duration<'de, D>(d: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = String::deserialize(d)?;
+
+    parse_duration(&raw).map(Some).map_err(de::Error::custom)
+}
+
+/// suites by name, each with its entries by name
+pub(crate) type Suites = BTreeMap<String, BTreeMap<String, SuiteEntry>>;
+
+impl SuiteEntry {
+    fn tags_and_options(
+        &self,
+        suite: &str,
+        name: &str,
+        dir: &Path,
+        config: &Config,
+        storage: &Storage,
+    ) -> (HashMap<String, String>, BenchmarkOptions) {
+        let mut tags = config.benchmark.tags.clone();
+        tags.extend(self.tags.clone());
+        tags.insert("suite".to_string(), suite.to_string());
+        tags.insert("name".to_string(), name.to_string());
+
+        let options = BenchmarkOptions {
+            runs: self.runs.or(config.benchmark.runs).unwrap_or(1),
+            execution: ExecutionOptions {
+                cwd: Some(
+                    self.cwd
+                        .as_ref()
+                        .map_or_else(|| dir.to_path_buf(), |cwd| dir.join(cwd)),
+                ),
+                env: self.env.clone(),
+                env_clear: self.env_clear,
+                env_keep: self.env_keep.clone(),
+                timeout: self.timeout,
+                ..Default::default()
+            },
+            setup: self.setup.clone(),
+            prepare: self.prepare.clone(),
+            cleanup: self.cleanup.clone(),
+            teardown: self.teardown.clone(),
+            machine: config.benchmark.machine.clone(),
+            tools: config.benchmark.tools.clone(),
+            storage: storage.clone(),
+            ..Default::default()
+        };
+
+        (tags, options)
+    }
+}
+
+/// runs all entries of the given suites, or of all suites, one after the other
+pub fn run_suites(names: &[String], all: bool, config: &Config, storage: &Storage) -> Result<()> {
+    let dir = project_dir_path();
+    let suites = load_suites(&dir)?;
+
+    for (suite, entries) in select_suites(&suites, names, all)? {
+        for (name, entry) in entries {
+            println!("Running {suite}/{name}");
+
+            let (tags, options) = entry.tags_and_options(suite, name, &dir, config, storage);
+
+            benchmark(&entry.command, &tags, &options)
+                .with_context(|| format!("failed to run {suite}/{name}"))?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn load_suites(dir: &Path) -> Result<Suites> {
+    let path = dir.join(SUITES_FILE);
+
+    let raw = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read suite file {}", path.display()))?;
+
+    let suites: Suites =
+        toml::from_str(&raw).with_context(|| format!("failed to parse {}", path.display()))?;
+
+    for (suite, entries) in &suites {
+        for (name, entry) in entries {
+            ensure!(
+                !entry.command.is_empty(),
+                "command of {suite}/{name} can not be empty"
+            );
+            ensure!(
+                entry.runs != Some(0),
+                "runs of {suite}/{name} have to be at least 1"
+            );
+        }
+    }
+
+    Ok(suites)
+}
+
+fn select_suites<'a>(
+    suites: &'a Suites,
+    names: &[String],
+    all: bool,
+) -> Result<Vec<(&'a String, &'a BTreeMap<String, SuiteEntry>)>> {
+    if all {
+        return Ok(suites.iter().collect());
+    }
+
+    names
+        .iter()
+        .map(|name| match suites.get_key_value(name) {
+            Some(suite) => Ok(suite),
+            None => bail!(
+                "unknown suite \"{}\", available suites: {}",
+                name,
+                suites.keys().join(", ")
+            ),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::tempdir;
+
+    const SUITES: &str = r#"
+[sorting.bubblesort]
+command = ["./sort", "--algorithm", "bubble"]
+tags = { algorithm = "bubblesort", suite = "overwritten" }
+cwd = "examples"
+env = { SIZE = "100" }
+env_clear = true
+env_keep = ["PATH"]
+setup = "make sort"
+runs = 3
+timeout = "1m"
+
+[sorting.mergesort]
+command = ["./sort", "--algorithm", "merge"]
+
+[startup.hello]
+command = ["echo", "hello"]
+"#;
+
+    fn suites() -> Suites {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(SUITES_FILE), SUITES).unwrap();
+
+        load_suites(dir.path()).unwrap()
+    }
+
+    #[test]
+    fn suites_are_selected_by_name() {
+        let suites = suites();
+
+        let selected = select_suites(&suites, &["startup".to_string()], false).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].0, "startup");
+
+        let selected = select_suites(&suites, &[], true).unwrap();
+        assert_eq!(selected.len(), 2);
+
+        assert!(select_suites(&suites, &["unknown".to_string()], false).is_err());
+    }
+
+    #[test]
+    fn entries_are_tagged_with_suite_and_name() {
+        let suites = suites();
+        let entry = &suites["sorting"]["bubblesort"];
+        let config = Config::default();
+        let storage = Storage::default();
+
+        let (tags, options) = entry.tags_and_options(
+            "sorting",
+            "bubblesort",
+            Path::new("/repo"),
+            &config,
+            &storage,
+        );
+
+        assert_eq!(tags["suite"], "sorting");
+        assert_eq!(tags["name"], "bubblesort");
+        assert_eq!(tags["algorithm"], "bubblesort");
+        assert_eq!(options.runs, 3);
+        assert_eq!(options.execution.cwd, Some(PathBuf::from("/repo/examples")));
+        assert_eq!(options.execution.env["SIZE"], "100");
+        assert!(options.execution.env_clear);
+        assert_eq!(options.execution.env_keep, vec!["PATH"]);
+        assert_eq!(options.setup.as_deref(), Some("make sort"));
+        assert_eq!(options.execution.timeout, Some(Duration::from_secs(60)));
+
+        let (_, options) = suites["sorting"]["mergesort"].tags_and_options(
+            "sorting",
+            "mergesort",
+            Path::new("/repo"),
+            &config,
+            &storage,
+        );
+        assert_eq!(options.runs, 1);
+    }
+
+    #[test]
+    fn entries_without_command_are_rejected() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join(SUITES_FILE),
+            "[suite.entry]\ncommand = []\n",
+        )
+        .unwrap();
+
+        assert!(load_suites(dir.path()).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use anyhow::{bail, Result};
+use itertools::Itertools;
+
+/// a parameter of a sweep with all its values, in the order given by the user
+pub type Param = (String, Vec<String>);
+
+/// one combination of parameter values, one value per parameter
+pub(crate) type Combination = Vec<(String, String)>;
+
+/// parses "name=value1,value2,..." into a parameter
+pub fn parse_param(raw: &str) -> Result<Param> {
+    let (name, values) = match raw.split_once('=') {
+        Some((name, values)) if !name.is_empty() => (name, values),
+        _ => bail!("parameter has to be of the form <name>=<value>,<value>,...: \"{raw}\""),
+    };
+
+    let values: Vec<_> = values.split(',').map(str::to_string).collect();
+
+    if values.iter().any(|v| v.is_empty()) {
+        bail!("parameter \"{name}\" has an empty value");
+    }
+
+    Ok((name.to_string(), values))
+}
+
+/// the cartesian product of all parameter values, the last parameter changes fastest.
+/// Without parameters, there is exactly one empty combination.
+pub(crate) fn expand(params: &[Param]) -> Vec<Combination> {
+    if params.is_empty() {
+        return vec![vec![]];
+    }
+
+    params
+        .iter()
+        .map(|(name, values)| values.iter().map(move |v| (name.clone(), v.clone())))
+        .multi_cartesian_product()
+        .collect()
+}
+
+/// replaces every `{name}` of a parameter in the command with the value of the combination
+pub(crate) fn substitute(command_and_flags: &[String], combination: &Combination) -> Vec<String> {
+    command_and_flags
+        .iter()
+        .map(|part| {
+            combination
+                .iter()
+                .fold(part.clone(), |part, (name, value)| {
+                    part.replace(&format!("{{{name}}}"), value)
+                })
+        })
+        .collect()
+}
+
+/// names of parameters which do not occur as `{name}` in the command
+pub(crate) fn unused_params<'a>(command_and_flags: &[String], params: &'a [Param]) -> Vec<&'a str> {
+    params
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .filter(|name| {
+            let placeholder = format!("{{{name}}}");
+            !command_and_flags.iter().any(|p| p.contains(&placeholder))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn params() -> Vec<Param> {
+        vec![
+            parse_param("n=10,100").unwrap(),
+            parse_param("algo=bubble,merge,quick").unwrap(),
+        ]
+    }
+
+    #[test]
+    fn params_are_parsed() {
+        assert_eq!(
+            parse_param("n=10,100").unwrap(),
+            ("n".to_string(), vec!["10".to_string(), "100".to_string()])
+        );
+        assert!(parse_param("n").is_err());
+        assert!(parse_param("=1").is_err());
+        assert!(parse_param("n=1,,2").is_err());
+    }
+
+    #[test]
+    fn expansion_is_the_cartesian_product() {
+        let combinations = expand(&params());
+
+        assert_eq!(combinations.len(), 6);
+        assert_eq!(expand(&[]), vec![Vec::<(String, String)>::new()]);
+        assert_eq!(
+            combinations[1],
+            vec![
+                ("n".to_string(), "10".to_string()),
+                ("algo".to_string(), "merge".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn placeholders_are_substituted() {
+        let command: Vec<String> = ["./sort", "--algo", "{algo}", "-n", "{n}", "{unknown}"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let substituted = substitute(&command, &expand(&params())[0]);
+
+        assert_eq!(
+            substituted,
+            vec!["./sort", "--algo", "bubble", "-n", "10", "{unknown}"]
+        );
+        assert!(unused_params(&command, &params()).is_empty());
+        assert_eq!(unused_params(&command[..3], &params()), vec!["n"]);
+    }
+}
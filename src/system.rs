@@ -1,8 +1,13 @@
+use crate::output::sha256;
 use crate::value;
 use bytesize::ByteSize;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
-use sysinfo::SystemExt;
+use std::fs;
+use std::path::Path;
+use sysinfo::{CpuExt, SystemExt};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct System {
@@ -28,6 +33,76 @@ pub struct System {
     pub arch: String,
     #[serde(with = "value")]
     pub benchie_version: String,
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cpu_model: Option<String>,
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub physical_cores: Option<i64>,
+    /// base frequency in MHz, only available with some CPU frequency drivers on Linux
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cpu_base_mhz: Option<i64>,
+    /// maximum frequency in MHz, including turbo boost
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cpu_max_mhz: Option<i64>,
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub cpu_governor: Option<String>,
+    /// whether simultaneous multithreading (Hyper-Threading) is active
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub smt: Option<bool>,
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub l1d_cache: Option<ByteSize>,
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub l1i_cache: Option<ByteSize>,
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub l2_cache: Option<ByteSize>,
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub l3_cache: Option<ByteSize>,
+    /// load average over the last minute when the benchmark started
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub load_average: Option<f64>,
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<bool>,
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub virtual_machine: Option<bool>,
+    /// label of the machine from `BENCHIE_MACHINE` or the configuration
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub machine_label: Option<String>,
+    /// stable ID of the hardware and the label, to not compare results of different machines
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub machine_id: Option<String>,
+}
+
+impl System {
+    /// sets the label and derives the machine ID from it and the hardware, but not from values
+    /// like the hostname or kernel version which change without affecting the results
+    ///
+    /// the memory is rounded to whole GiB, as the kernel reserves a varying part of it
+    pub(crate) fn identify(&mut self, label: Option<String>) {
+        let memory_gib =
+            (self.total_memory.as_u64() as f64 / ByteSize::gib(1).as_u64() as f64).round() as u64;
+        let format = |value: &Option<ByteSize>| value.map(|v| v.as_u64().to_string());
+        let fields = [
+            Some(self.arch.clone()),
+            Some(self.os.clone()),
+            self.cpu_model.clone(),
+            Some(self.cores.to_string()),
+            self.physical_cores.map(|cores| cores.to_string()),
+            Some(memory_gib.to_string()),
+            format(&self.l1d_cache),
+            format(&self.l1i_cache),
+            format(&self.l2_cache),
+            format(&self.l3_cache),
+            label.clone(),
+        ];
+        let identity = fields
+            .iter()
+            .map(|field| field.as_deref().unwrap_or(""))
+            .join("\n");
+
+        self.machine_id = Some(sha256(identity.as_bytes())[..16].to_string());
+        self.machine_label = label;
+    }
 }
 
 impl Default for System {
@@ -40,6 +115,8 @@ impl Default for System {
 
         system.refresh_all();
 
+        let caches = read_caches();
+
         Self {
             total_memory: ByteSize::kb(system.total_memory()),
             used_memory: ByteSize::kb(system.used_memory()),
@@ -60,10 +137,105 @@ impl Default for System {
             benchie_version: option_env!("CARGO_PKG_VERSION")
                 .unwrap_or("not found")
                 .to_owned(),
+            hostname: system.host_name(),
+            cpu_model: system
+                .cpus()
+                .first()
+                .map(|cpu| cpu.brand().trim().to_owned())
+                .filter(|brand| !brand.is_empty()),
+            physical_cores: system
+                .physical_core_count()
+                .and_then(|cores| cores.try_into().ok()),
+            cpu_base_mhz: read_khz_as_mhz(&format!("{CPU}/cpu0/cpufreq/base_frequency")),
+            cpu_max_mhz: read_khz_as_mhz(&format!("{CPU}/cpu0/cpufreq/cpuinfo_max_freq")),
+            cpu_governor: read_sys(&format!("{CPU}/cpu0/cpufreq/scaling_governor")),
+            smt: read_sys(&format!("{CPU}/smt/active")).map(|active| active == "1"),
+            l1d_cache: caches.get(&(1, "Data".to_owned())).copied(),
+            l1i_cache: caches.get(&(1, "Instruction".to_owned())).copied(),
+            l2_cache: caches.get(&(2, "Unified".to_owned())).copied(),
+            l3_cache: caches.get(&(3, "Unified".to_owned())).copied(),
+            load_average: cfg!(unix).then(|| system.load_average().one),
+            container: cfg!(target_os = "linux").then(is_container),
+            virtual_machine: cfg!(target_os = "linux").then(is_virtual_machine),
+            machine_label: None,
+            machine_id: None,
         }
     }
 }
 
+const CPU: &str = "/sys/devices/system/cpu";
+
+/// a trimmed value from a file in `/sys` or `/proc`, only available on Linux
+fn read_sys(path: &str) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_owned())
+}
+
+fn read_khz_as_mhz(path: &str) -> Option<i64> {
+    read_sys(path)?.parse::<i64>().ok().map(|khz| khz / 1000)
+}
+
+/// sizes of the caches of the first CPU by level and type, e.g. `(1, "Data")`
+fn read_caches() -> HashMap<(i64, String), ByteSize> {
+    (0..8)
+        .map_while(|i| {
+            let index = format!("{CPU}/cpu0/cache/index{i}");
+            let level = read_sys(&format!("{index}/level"))?.parse().ok()?;
+            let kind = read_sys(&format!("{index}/type"))?;
+            let size = read_sys(&format!("{index}/size"))?;
+
+            Some(parse_cache_size(&size).map(|size| ((level, kind), size)))
+        })
+        .flatten()
+        .collect()
+}
+
+/// sizes like `48K` or `32M`, as written by Linux
+fn parse_cache_size(size: &str) -> Option<ByteSize> {
+    let (number, unit) = size.split_at(size.find(|c: char| !c.is_ascii_digit())?);
+    let number: u64 = number.parse().ok()?;
+
+    match unit {
+        "K" => Some(ByteSize::kib(number)),
+        "M" => Some(ByteSize::mib(number)),
+        "G" => Some(ByteSize::gib(number)),
+        _ => None,
+    }
+}
+
+fn is_container() -> bool {
+    Path::new("/.dockerenv").exists()
+        || Path::new("/run/.containerenv").exists()
+        || read_sys("/proc/1/cgroup").is_some_and(|cgroup| {
+            ["docker", "kubepods", "lxc", "containerd", "libpod"]
+                .iter()
+                .any(|runtime| cgroup.contains(runtime))
+        })
+}
+
+fn is_virtual_machine() -> bool {
+    let hypervisor_flag = read_sys("/proc/cpuinfo").is_some_and(|cpuinfo| {
+        cpuinfo
+            .lines()
+            .filter(|line| line.starts_with("flags"))
+            .any(|line| line.split_whitespace().any(|flag| flag == "hypervisor"))
+    });
+    let vendor = read_sys("/sys/class/dmi/id/sys_vendor").unwrap_or_default();
+
+    hypervisor_flag
+        || [
+            "QEMU",
+            "KVM",
+            "VMware",
+            "VirtualBox",
+            "Xen",
+            "Microsoft Corporation",
+        ]
+        .iter()
+        .any(|hypervisor| vendor.contains(hypervisor))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -81,5 +253,54 @@ mod test {
         assert!(!s.kernel_version.is_empty());
         assert!(matches!(s.arch.as_str(), "aarch64" | "x86_64"));
         assert!(s.benchie_version.len() >= 5 && s.benchie_version.contains('.'));
+        assert!(s.physical_cores.unwrap_or(1) <= s.cores);
+        if cfg!(target_os = "linux") {
+            assert!(s.container.is_some() && s.virtual_machine.is_some());
+        }
+    }
+
+    #[test]
+    fn machine_id_depends_on_hardware_and_label() {
+        let mut system = System::default();
+        system.identify(None);
+        let id = system.machine_id.clone().unwrap();
+
+        system.hostname = Some("renamed".to_string());
+        system.load_average = Some(42.0);
+        system.identify(None);
+        assert_eq!(system.machine_id.as_ref(), Some(&id));
+        assert_eq!(id.len(), 16);
+
+        system.identify(Some("lab-1".to_string()));
+        assert_ne!(system.machine_id.as_ref(), Some(&id));
+        assert_eq!(system.machine_label.as_deref(), Some("lab-1"));
+
+        system.identify(None);
+        system.total_memory = ByteSize::gib(1024);
+        system.identify(None);
+        assert_ne!(system.machine_id.as_ref(), Some(&id));
+    }
+
+    #[test]
+    fn machine_id_ignores_small_changes_of_memory() {
+        let mut system = System {
+            total_memory: ByteSize::kb(16_318_404),
+            ..Default::default()
+        };
+        system.identify(None);
+        let id = system.machine_id.clone();
+
+        // e.g. after a kernel update reserves a few MB more
+        system.total_memory = ByteSize::kb(16_297_212);
+        system.identify(None);
+        assert_eq!(system.machine_id, id);
+    }
+
+    #[test]
+    fn cache_sizes_are_parsed() {
+        assert_eq!(parse_cache_size("48K"), Some(ByteSize::kib(48)));
+        assert_eq!(parse_cache_size("32M"), Some(ByteSize::mib(32)));
+        assert_eq!(parse_cache_size("512"), None);
+        assert_eq!(parse_cache_size("1T"), None);
     }
 }
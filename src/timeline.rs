@@ -0,0 +1,130 @@
+use crate::value;
+use bytesize::ByteSize;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// memory and CPU usage of the command and its descendants at one point in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Sample {
+    /// time since the command was spawned
+    pub elapsed: Duration,
+    /// resident set size summed over the process tree
+    pub rss: u64,
+    /// user and system time summed over the process tree
+    pub cpu_time: Duration,
+}
+
+/// summary of the samples taken while the command was running and the samples themselves
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Timeline {
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub avg_rss: Option<ByteSize>,
+
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub peak_rss: Option<ByteSize>,
+
+    /// time from spawning the command until the first sample with the peak RSS
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub time_to_peak_rss: Option<Duration>,
+
+    /// CPU usage in percent of one CPU, above 100 if the command uses multiple CPUs
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub avg_cpu: Option<f64>,
+
+    /// space-separated samples of `<elapsed ms>:<RSS in KiB>:<CPU % since the last sample>`
+    #[serde(with = "value", default, skip_serializing_if = "Option::is_none")]
+    pub timeline: Option<String>,
+}
+
+impl Timeline {
+    pub(crate) fn new(samples: &[Sample]) -> Self {
+        let (first, last) = match (samples.first(), samples.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Self::default(),
+        };
+
+        let peak = samples
+            .iter()
+            .rev()
+            .max_by_key(|sample| sample.rss)
+            .expect("samples are not empty");
+
+        let timeline = std::iter::once((first, 0.0))
+            .chain(
+                samples
+                    .iter()
+                    .tuple_windows()
+                    .map(|(previous, sample)| (sample, cpu_usage(previous, sample))),
+            )
+            .map(|(sample, cpu)| {
+                format!(
+                    "{}:{}:{:.0}",
+                    sample.elapsed.as_millis(),
+                    sample.rss / 1024,
+                    cpu
+                )
+            })
+            .join(" ");
+
+        Self {
+            avg_rss: Some(ByteSize::b(
+                samples.iter().map(|sample| sample.rss).sum::<u64>() / samples.len() as u64,
+            )),
+            peak_rss: Some(ByteSize::b(peak.rss)),
+            time_to_peak_rss: Some(peak.elapsed),
+            avg_cpu: (samples.len() > 1).then(|| cpu_usage(first, last)),
+            timeline: Some(timeline),
+        }
+    }
+}
+
+/// CPU usage in percent between two samples
+fn cpu_usage(previous: &Sample, sample: &Sample) -> f64 {
+    let elapsed = sample.elapsed.saturating_sub(previous.elapsed);
+    if elapsed.is_zero() {
+        return 0.0;
+    }
+
+    let cpu_time = sample.cpu_time.saturating_sub(previous.cpu_time);
+
+    cpu_time.as_secs_f64() / elapsed.as_secs_f64() * 100.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample(elapsed_ms: u64, rss_kib: u64, cpu_time_ms: u64) -> Sample {
+        Sample {
+            elapsed: Duration::from_millis(elapsed_ms),
+            rss: rss_kib * 1024,
+            cpu_time: Duration::from_millis(cpu_time_ms),
+        }
+    }
+
+    #[test]
+    fn samples_are_summarized() {
+        let timeline = Timeline::new(&[
+            sample(0, 100, 0),
+            sample(10, 300, 10),
+            sample(20, 300, 15),
+            sample(30, 200, 15),
+        ]);
+
+        assert_eq!(timeline.avg_rss, Some(ByteSize::kib(225)));
+        assert_eq!(timeline.peak_rss, Some(ByteSize::kib(300)));
+        assert_eq!(timeline.time_to_peak_rss, Some(Duration::from_millis(10)));
+        assert_eq!(timeline.avg_cpu, Some(50.0));
+        assert_eq!(
+            timeline.timeline.as_deref(),
+            Some("0:100:0 10:300:100 20:300:50 30:200:0")
+        );
+    }
+
+    #[test]
+    fn no_samples_give_empty_timeline() {
+        assert_eq!(Timeline::new(&[]), Timeline::default());
+        assert_eq!(Timeline::new(&[sample(0, 1, 0)]).avg_cpu, None);
+    }
+}
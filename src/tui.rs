@@ -0,0 +1,637 @@
+use crate::show::{
+    apply_filter, compute_2d_table_data, compute_key_infos, compute_table_data_1d, format_2d_rows,
+    group_1d_table_data, mixed_machines,
+};
+use crate::value::Aggregation;
+use crate::{BenchmarkRaw, Storage, Values};
+use anyhow::{bail, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::io;
+use tui::backend::{Backend, CrosstermBackend};
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table};
+use tui::{Frame, Terminal};
+
+const HELP_RUNS: &str =
+    "q quit | tab table view | ↑↓ select | space mark for compare | / edit filter";
+const HELP_TABLE: &str = "q quit | tab runs view | r row | c col | m metric | x clear col | a aggregation | / edit filter";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    Runs,
+    Table,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Row,
+    Col,
+    Metric,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Input {
+    None,
+    Filter(String),
+    PickKey(Dimension, usize),
+}
+
+struct App {
+    benchmarks: Vec<BenchmarkRaw>,
+    filter_text: String,
+    filter: HashMap<String, String>,
+    /// indices of the benchmarks passing the filter, newest first
+    visible: Vec<usize>,
+    selected: usize,
+    marked: Option<usize>,
+    keys: Vec<String>,
+    row: Option<String>,
+    col: Option<String>,
+    metric: Option<String>,
+    aggregation: Option<Aggregation>,
+    view: View,
+    input: Input,
+    status: String,
+}
+
+impl App {
+    fn new(benchmarks: Vec<BenchmarkRaw>) -> Self {
+        let mut app = Self {
+            benchmarks,
+            filter_text: String::new(),
+            filter: HashMap::new(),
+            visible: vec![],
+            selected: 0,
+            marked: None,
+            keys: vec![],
+            row: None,
+            col: None,
+            metric: None,
+            aggregation: None,
+            view: View::Runs,
+            input: Input::None,
+            status: String::new(),
+        };
+
+        app.refresh();
+        app.metric = app.keys.iter().find(|k| *k == "real_time").cloned();
+        app.row = app.keys.iter().find(|k| *k == "command").cloned();
+
+        app
+    }
+
+    fn refresh(&mut self) {
+        self.visible = (0..self.benchmarks.len())
+            .rev()
+            .filter(|i| apply_filter(&self.benchmarks[*i], &self.filter))
+            .collect();
+        self.selected = self.selected.min(self.visible.len().saturating_sub(1));
+
+        self.keys = compute_key_infos(self.visible.iter().rev().map(|i| &self.benchmarks[*i]), 0)
+            .into_keys()
+            .sorted()
+            .collect();
+    }
+
+    fn set_filter(&mut self, text: &str) -> Result<()> {
+        let mut filter = HashMap::new();
+
+        for predicate in text.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match predicate.split_once('=') {
+                Some((key, value)) if !key.is_empty() && !value.is_empty() => {
+                    filter.insert(key.to_string(), value.to_string());
+                }
+                _ => bail!("filter has to be a <key>=<value> pair: \"{predicate}\""),
+            }
+        }
+
+        self.filter_text = text.to_string();
+        self.filter = filter;
+        self.refresh();
+
+        Ok(())
+    }
+
+    fn selected_benchmark(&self) -> Option<usize> {
+        self.visible.get(self.selected).cloned()
+    }
+
+    /// handles a key press and returns true if the application should quit
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        match self.input.clone() {
+            Input::Filter(mut text) => {
+                match code {
+                    KeyCode::Enter => match self.set_filter(&text) {
+                        Ok(()) => {
+                            self.status.clear();
+                            self.input = Input::None;
+                        }
+                        Err(error) => self.status = error.to_string(),
+                    },
+                    KeyCode::Esc => self.input = Input::None,
+                    KeyCode::Backspace => {
+                        text.pop();
+                        self.input = Input::Filter(text);
+                    }
+                    KeyCode::Char(c) => {
+                        text.push(c);
+                        self.input = Input::Filter(text);
+                    }
+                    _ => {}
+                }
+                false
+            }
+            Input::PickKey(dimension, index) => {
+                match code {
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        self.input = Input::PickKey(dimension, index.saturating_sub(1))
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        let last = self.keys.len().saturating_sub(1);
+                        self.input = Input::PickKey(dimension, (index + 1).min(last))
+                    }
+                    KeyCode::Enter => {
+                        let key = self.keys.get(index).cloned();
+                        match dimension {
+                            Dimension::Row => self.row = key,
+                            Dimension::Col => self.col = key,
+                            Dimension::Metric => self.metric = key,
+                        }
+                        self.input = Input::None;
+                    }
+                    KeyCode::Esc => self.input = Input::None,
+                    _ => {}
+                }
+                false
+            }
+            Input::None => match code {
+                KeyCode::Char('q') | KeyCode::Esc => true,
+                KeyCode::Tab => {
+                    self.view = match self.view {
+                        View::Runs => View::Table,
+                        View::Table => View::Runs,
+                    };
+                    false
+                }
+                KeyCode::Char('/') => {
+                    self.input = Input::Filter(self.filter_text.clone());
+                    false
+                }
+                code if self.view == View::Runs => {
+                    self.handle_runs_key(code);
+                    false
+                }
+                code => {
+                    self.handle_table_key(code);
+                    false
+                }
+            },
+        }
+    }
+
+    fn handle_runs_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.visible.len().saturating_sub(1))
+            }
+            KeyCode::Char(' ') => {
+                let selected = self.selected_benchmark();
+                self.marked = if self.marked == selected {
+                    None
+                } else {
+                    selected
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_table_key(&mut self, code: KeyCode) {
+        let pick = |current: &Option<String>, keys: &[String]| {
+            current
+                .as_ref()
+                .and_then(|c| keys.iter().position(|k| k == c))
+                .unwrap_or(0)
+        };
+
+        match code {
+            KeyCode::Char('r') => {
+                self.input = Input::PickKey(Dimension::Row, pick(&self.row, &self.keys))
+            }
+            KeyCode::Char('c') => {
+                self.input = Input::PickKey(Dimension::Col, pick(&self.col, &self.keys))
+            }
+            KeyCode::Char('m') => {
+                self.input = Input::PickKey(Dimension::Metric, pick(&self.metric, &self.keys))
+            }
+            KeyCode::Char('x') => self.col = None,
+            KeyCode::Char('a') => {
+                self.aggregation = match self.aggregation {
+                    None => Some(Aggregation::Mean),
+                    Some(Aggregation::Mean) => Some(Aggregation::Median),
+                    Some(Aggregation::Median) => Some(Aggregation::Min),
+                    Some(Aggregation::Min) => Some(Aggregation::Max),
+                    Some(Aggregation::Max) => None,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// key value pairs of the selected benchmark, or of the marked and selected benchmark side
+    /// by side if a benchmark is marked for comparison
+    fn detail_rows(&self) -> Vec<Vec<String>> {
+        let selected = match self.selected_benchmark() {
+            Some(selected) => &self.benchmarks[selected],
+            None => return vec![],
+        };
+        let marked = self.marked.map(|m| &self.benchmarks[m]);
+
+        let keys = selected
+            .data
+            .keys()
+            .chain(marked.iter().flat_map(|m| m.data.keys()))
+            .unique()
+            .sorted();
+
+        let display = |b: &BenchmarkRaw, key: &str| {
+            b.data.get(key).map_or_else(String::new, |v| v.to_string())
+        };
+
+        keys.map(|key| match marked {
+            Some(marked) => vec![key.clone(), display(marked, key), display(selected, key)],
+            None => vec![key.clone(), display(selected, key)],
+        })
+        .collect()
+    }
+
+    /// the table compares benchmarks of different machines, unless they are grouped by machine
+    fn machines_warning(&self) -> Option<String> {
+        let grouped_by = [self.row.as_deref(), self.col.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect_vec();
+
+        mixed_machines(&self.benchmarks, &self.filter, &grouped_by)
+    }
+
+    fn table(&self) -> Option<(Vec<String>, Vec<Vec<String>>)> {
+        let row = self.row.as_deref()?;
+        let metric = self.metric.as_deref()?;
+
+        let format = |values: &Values| match self.aggregation {
+            Some(aggregation) => values
+                .aggregate(aggregation)
+                .map_or_else(|| values.to_string(), |v| v.to_string()),
+            None => values.to_string(),
+        };
+
+        Some(match self.col.as_deref() {
+            Some(col) => {
+                let data = compute_2d_table_data(&self.benchmarks, row, col, metric, &self.filter);
+                let mut headers = data.table_headers.clone();
+                headers[0] = row.to_string();
+
+                (headers, format_2d_rows(&data, format))
+            }
+            None => {
+                let data = compute_table_data_1d(&self.benchmarks, row, metric, &self.filter);
+                let rows = group_1d_table_data(data)
+                    .iter()
+                    .map(|(label, values)| vec![label.clone(), format(values)])
+                    .collect();
+
+                (vec![row.to_string(), metric.to_string()], rows)
+            }
+        })
+    }
+}
+
+/// starts the interactive terminal UI over all stored benchmarks
+pub fn tui(storage: &Storage) -> Result<()> {
+    let mut app = App::new(storage.load_all_benchmarks()?);
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let result = run(&mut terminal, &mut app);
+
+    // always restore the terminal, even if the application failed
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    result
+}
+
+fn run<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
+    loop {
+        terminal.draw(|f| draw(f, app))?;
+
+        if let Event::Key(KeyEvent { code, .. }) = event::read()? {
+            if app.handle_key(code) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn draw<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(3)])
+        .split(f.size());
+
+    match app.view {
+        View::Runs => draw_runs(f, app, chunks[0]),
+        View::Table => draw_table(f, app, chunks[0]),
+    }
+
+    let warning = match app.view {
+        View::Table => app.machines_warning(),
+        View::Runs => None,
+    };
+    let (title, text) = match (&app.input, warning) {
+        (Input::Filter(text), _) => ("filter (enter to apply, esc to cancel)", format!("{text}▏")),
+        _ if !app.status.is_empty() => ("error", app.status.clone()),
+        (_, Some(warning)) => ("warning", warning),
+        _ => (
+            "help",
+            match app.view {
+                View::Runs => HELP_RUNS,
+                View::Table => HELP_TABLE,
+            }
+            .to_string(),
+        ),
+    };
+    let footer = Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(footer, chunks[1]);
+
+    if let Input::PickKey(dimension, index) = app.input {
+        draw_key_picker(f, app, dimension, index);
+    }
+}
+
+fn draw_runs<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(area);
+
+    let items: Vec<_> = app
+        .visible
+        .iter()
+        .map(|i| {
+            let b = &app.benchmarks[*i];
+            let get = |key: &str| b.data.get(key).map_or_else(String::new, |v| v.to_string());
+            let mark = if app.marked == Some(*i) { "*" } else { " " };
+
+            ListItem::new(format!(
+                "{mark}#{i:<4} {}  {}",
+                get("created_at"),
+                get("command")
+            ))
+        })
+        .collect();
+
+    let title = if app.filter.is_empty() {
+        format!("runs ({})", app.visible.len())
+    } else {
+        format!(
+            "runs ({}) filtered by {}",
+            app.visible.len(),
+            app.filter_text
+        )
+    };
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default();
+    state.select(app.selected_benchmark().map(|_| app.selected));
+    f.render_stateful_widget(list, chunks[0], &mut state);
+
+    let rows = app.detail_rows();
+    let (title, header, widths) = match (app.marked, app.selected_benchmark()) {
+        (Some(marked), Some(selected)) => (
+            format!("compare #{marked} with #{selected}"),
+            vec![
+                "key".to_string(),
+                format!("#{marked}"),
+                format!("#{selected}"),
+            ],
+            vec![
+                Constraint::Percentage(30),
+                Constraint::Percentage(35),
+                Constraint::Percentage(35),
+            ],
+        ),
+        _ => (
+            "details".to_string(),
+            vec!["key".to_string(), "value".to_string()],
+            vec![Constraint::Percentage(30), Constraint::Percentage(70)],
+        ),
+    };
+
+    let rows = rows.into_iter().map(|cells| {
+        let differs = cells.len() == 3 && cells[1] != cells[2];
+        let style = if differs {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        Row::new(cells.into_iter().map(Cell::from)).style(style)
+    });
+
+    let table = Table::new(rows)
+        .header(Row::new(header).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .widths(&widths);
+    f.render_widget(table, chunks[1]);
+}
+
+fn draw_table<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let describe = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+    let title = format!(
+        "row: {}, col: {}, metric: {}, aggregation: {}",
+        describe(&app.row),
+        describe(&app.col),
+        describe(&app.metric),
+        app.aggregation
+            .map_or_else(|| "none".to_string(), |a| a.to_string())
+    );
+    let block = Block::default().borders(Borders::ALL).title(title);
+
+    match app.table() {
+        Some((headers, rows)) if !rows.is_empty() => {
+            let widths: Vec<_> = (0..headers.len())
+                .map(|_| Constraint::Ratio(1, headers.len() as u32))
+                .collect();
+            let table = Table::new(rows.into_iter().map(Row::new))
+                .header(Row::new(headers).style(Style::default().add_modifier(Modifier::BOLD)))
+                .block(block)
+                .widths(&widths);
+            f.render_widget(table, area);
+        }
+        Some(_) => f.render_widget(Paragraph::new("Result is empty").block(block), area),
+        None => f.render_widget(
+            Paragraph::new("select a row (r) and a metric (m) to display a table").block(block),
+            area,
+        ),
+    }
+}
+
+fn draw_key_picker<B: Backend>(f: &mut Frame<B>, app: &App, dimension: Dimension, index: usize) {
+    let area = f.size();
+    let popup = Rect {
+        x: area.width / 4,
+        y: area.height / 6,
+        width: area.width / 2,
+        height: area.height * 2 / 3,
+    };
+
+    let title = match dimension {
+        Dimension::Row => "select row",
+        Dimension::Col => "select col",
+        Dimension::Metric => "select metric",
+    };
+
+    let items: Vec<_> = app
+        .keys
+        .iter()
+        .map(|k| ListItem::new(Spans::from(Span::raw(k.clone()))))
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default();
+    state.select(Some(index));
+
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(list, popup, &mut state);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Value;
+
+    fn app() -> App {
+        let benchmarks = [("ls", 1), ("ls", 2), ("cat", 3)]
+            .iter()
+            .map(|(command, time)| {
+                let mut b = BenchmarkRaw::default();
+                b.data
+                    .insert("command".to_string(), Value::String(command.to_string()));
+                b.data
+                    .insert("real_time".to_string(), Value::Integer(*time));
+                b
+            })
+            .collect();
+
+        App::new(benchmarks)
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        text.chars().for_each(|c| {
+            app.handle_key(KeyCode::Char(c));
+        });
+    }
+
+    #[test]
+    fn runs_are_listed_newest_first() {
+        let app = app();
+
+        assert_eq!(app.visible, vec![2, 1, 0]);
+        assert_eq!(app.keys, vec!["command", "real_time"]);
+    }
+
+    #[test]
+    fn filter_can_be_edited_live() {
+        let mut app = app();
+
+        app.handle_key(KeyCode::Char('/'));
+        type_text(&mut app, "command=ls");
+        app.handle_key(KeyCode::Enter);
+
+        assert_eq!(app.input, Input::None);
+        assert_eq!(app.visible, vec![1, 0]);
+
+        app.handle_key(KeyCode::Char('/'));
+        type_text(&mut app, ", broken");
+        app.handle_key(KeyCode::Enter);
+
+        assert!(!app.status.is_empty(), "invalid filter should be reported");
+        assert_eq!(
+            app.visible,
+            vec![1, 0],
+            "invalid filter should not be applied"
+        );
+    }
+
+    #[test]
+    fn marked_run_is_compared_side_by_side() {
+        let mut app = app();
+
+        app.handle_key(KeyCode::Char(' '));
+        app.handle_key(KeyCode::Down);
+
+        assert_eq!(app.marked, Some(2));
+        assert_eq!(
+            app.detail_rows(),
+            vec![
+                vec!["command".to_string(), "cat".to_string(), "ls".to_string()],
+                vec!["real_time".to_string(), "3".to_string(), "2".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn comparing_different_machines_is_warned_about() {
+        let mut app = app();
+        assert_eq!(app.machines_warning(), None);
+
+        for (i, b) in app.benchmarks.iter_mut().enumerate() {
+            b.data.insert(
+                "machine_id".to_string(),
+                Value::String(format!("m{}", i % 2)),
+            );
+        }
+        assert_eq!(
+            app.machines_warning().as_deref(),
+            Some("benchmarks of 2 machines are compared (m0, m1)")
+        );
+
+        app.col = Some("machine_id".to_string());
+        assert_eq!(app.machines_warning(), None);
+    }
+
+    #[test]
+    fn table_keys_are_selected_from_discovered_keys() {
+        let mut app = app();
+
+        app.handle_key(KeyCode::Tab);
+        app.handle_key(KeyCode::Char('a'));
+
+        let (headers, rows) = app.table().expect("command and real_time are preselected");
+        assert_eq!(headers, vec!["command", "real_time"]);
+        assert_eq!(rows[1], vec!["ls", "1.5"]);
+
+        app.handle_key(KeyCode::Char('c'));
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+
+        assert_eq!(app.col.as_deref(), Some("real_time"));
+    }
+}
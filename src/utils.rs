@@ -1,3 +1,6 @@
+use anyhow::{bail, Context, Result};
+use std::time::Duration;
+
 pub fn parse_key_value_pair(v: &str) -> (String, String) {
     let mut it = v.split('=');
     let key = it.next().expect("already validated").to_owned();
@@ -9,8 +12,127 @@ pub fn parse_key_value_pair(v: &str) -> (String, String) {
 pub fn is_key_value_pair(v: &str) -> Result<(), String> {
     let kv: Vec<_> = v.split('=').collect();
 
-    match (kv.get(0), kv.get(1)) {
+    match (kv.first(), kv.get(1)) {
         (Some(key), Some(value)) if !key.is_empty() && !value.is_empty() => Ok(()),
         _ => Err(String::from("tag has to be a <key>=<value> pair")),
     }
 }
+
+/// parses a duration like "500ms", "30s", "1.5m" or "2h", a number without unit is in seconds
+pub fn parse_duration(v: &str) -> Result<Duration> {
+    let v = v.trim();
+    let split = v
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(v.len());
+    let (number, unit) = v.split_at(split);
+
+    let number: f64 = number
+        .parse()
+        .with_context(|| format!("invalid duration \"{v}\""))?;
+
+    let seconds = match unit.trim() {
+        "ns" => number / 1e9,
+        "us" | "µs" => number / 1e6,
+        "ms" => number / 1e3,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        unit => bail!(
+            "unknown unit \"{unit}\" of duration \"{v}\", expected one of: ns, us, ms, s, m, h"
+        ),
+    };
+
+    Duration::try_from_secs_f64(seconds).with_context(|| format!("duration \"{v}\" is too long"))
+}
+
+/// parses a list of CPUs like "0,2,4-7"
+pub fn parse_cpu_list(v: &str) -> Result<Vec<usize>> {
+    let mut cpus = vec![];
+
+    for part in v.split(',') {
+        let parse = |cpu: &str| {
+            cpu.trim()
+                .parse::<usize>()
+                .with_context(|| format!("invalid CPU \"{cpu}\" in \"{v}\""))
+        };
+
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (parse(first)?, parse(last)?);
+                if first > last {
+                    bail!("invalid CPU range \"{part}\" in \"{v}\"");
+                }
+                cpus.extend(first..=last);
+            }
+            None => cpus.push(parse(part)?),
+        }
+    }
+
+    cpus.sort_unstable();
+    cpus.dedup();
+
+    Ok(cpus)
+}
+
+/// whether the name matches the pattern, in which `*` matches any sequence of characters
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+
+    let mut rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let parts: Vec<_> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some((last, middle)) => (*last, middle),
+        // no wildcard in the pattern
+        None => return rest.is_empty(),
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cpu_lists_are_parsed_with_ranges() {
+        assert_eq!(parse_cpu_list("3").unwrap(), vec![3]);
+        assert_eq!(parse_cpu_list("4-6,0,5").unwrap(), vec![0, 4, 5, 6]);
+        assert!(parse_cpu_list("2-1").is_err());
+        assert!(parse_cpu_list("a").is_err());
+        assert!(parse_cpu_list("").is_err());
+    }
+
+    #[test]
+    fn patterns_match_with_wildcards() {
+        assert!(matches_pattern("PATH", "PATH"));
+        assert!(!matches_pattern("PATH", "PATHS"));
+        assert!(matches_pattern("LC_*", "LC_ALL"));
+        assert!(matches_pattern("*_PROXY", "HTTP_PROXY"));
+        assert!(matches_pattern("C*GO*", "CARGO_HOME_GO"));
+        assert!(!matches_pattern("LC_*", "LANG"));
+        assert!(matches_pattern("*", "ANYTHING"));
+    }
+
+    #[test]
+    fn durations_are_parsed_with_units() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("1.5m").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+        assert!(parse_duration("10 parsecs").is_err());
+        assert!(parse_duration("ms").is_err());
+        assert!(parse_duration("99999999999999999999999h").is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::benchmark::ExecutionResult;
+use crate::output::{sha256, CommandOutput};
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+
+/// what a correct run of the benchmarked command has to produce
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Expectations {
+    pub exit_code: Option<i64>,
+    /// file with the exact expected stdout
+    pub stdout_file: Option<PathBuf>,
+    /// hex encoded SHA-256 hash of the expected stdout
+    pub stdout_hash: Option<String>,
+}
+
+impl Expectations {
+    pub fn is_empty(&self) -> bool {
+        self.exit_code.is_none() && self.stdout_file.is_none() && self.stdout_hash.is_none()
+    }
+
+    pub(crate) fn checks_stdout(&self) -> bool {
+        self.stdout_file.is_some() || self.stdout_hash.is_some()
+    }
+
+    /// describes every expectation the run did not meet, empty if the run is valid
+    pub(crate) fn violations(
+        &self,
+        result: &ExecutionResult,
+        output: &CommandOutput,
+    ) -> Result<Vec<String>> {
+        let mut violations = vec![];
+
+        if let Some(expected) = self.exit_code {
+            match (result.exit_code, result.signal_name.as_ref(), result.signal) {
+                (Some(code), _, _) if code == expected => {}
+                (Some(code), _, _) => {
+                    violations.push(format!("exit code was {code} instead of {expected}"))
+                }
+                (None, Some(name), _) => violations.push(format!(
+                    "terminated by {name} instead of exiting with {expected}"
+                )),
+                (None, None, signal) => violations.push(format!(
+                    "terminated by signal {} instead of exiting with {expected}",
+                    signal.unwrap_or_default()
+                )),
+            }
+        }
+
+        if let Some(path) = &self.stdout_file {
+            let expected = fs::read(path)
+                .with_context(|| format!("failed to read expected stdout {}", path.display()))?;
+
+            if expected != output.stdout {
+                violations.push(format!("stdout differs from {}", path.display()));
+            }
+        }
+
+        if let Some(expected) = &self.stdout_hash {
+            let hash = sha256(&output.stdout);
+
+            if !hash.eq_ignore_ascii_case(expected.trim()) {
+                violations.push(format!(
+                    "stdout has hash {hash} instead of {}",
+                    expected.trim()
+                ));
+            }
+        }
+
+        Ok(violations)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn output(stdout: &str) -> CommandOutput {
+        CommandOutput {
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: None,
+        }
+    }
+
+    fn exited_with(code: i64) -> ExecutionResult {
+        ExecutionResult {
+            exit_code: Some(code),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn exit_code_is_checked() {
+        let expectations = Expectations {
+            exit_code: Some(0),
+            ..Default::default()
+        };
+
+        let violations = expectations
+            .violations(&exited_with(0), &output(""))
+            .unwrap();
+        assert!(violations.is_empty());
+
+        let violations = expectations
+            .violations(&exited_with(1), &output(""))
+            .unwrap();
+        assert_eq!(violations, vec!["exit code was 1 instead of 0"]);
+
+        let killed = ExecutionResult {
+            signal: Some(9),
+            signal_name: Some("SIGKILL".to_string()),
+            ..Default::default()
+        };
+        let violations = expectations.violations(&killed, &output("")).unwrap();
+        assert_eq!(
+            violations,
+            vec!["terminated by SIGKILL instead of exiting with 0"]
+        );
+    }
+
+    #[test]
+    fn stdout_is_compared_with_file_and_hash() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("expected");
+        fs::write(&path, "hello\n").unwrap();
+
+        let expectations = Expectations {
+            stdout_file: Some(path),
+            stdout_hash: Some(
+                "5891B5B522D5DF086D0FF0B110FBD9D21BB4FC7163AF34D08286A2E846F6BE03".to_string(),
+            ),
+            ..Default::default()
+        };
+
+        let violations = expectations
+            .violations(&exited_with(0), &output("hello\n"))
+            .unwrap();
+        assert!(violations.is_empty());
+
+        let violations = expectations
+            .violations(&exited_with(0), &output("hello"))
+            .unwrap();
+        assert_eq!(violations.len(), 2);
+    }
+}
@@ -1,9 +1,11 @@
 use anyhow::anyhow;
 use bytesize::ByteSize;
 use chrono::prelude::*;
+use itertools::Itertools;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 use std::fmt::Debug;
+use std::str::FromStr;
 use std::time::Duration;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -32,12 +34,248 @@ impl fmt::Display for Value {
     }
 }
 
+impl Value {
+    /// infers the type of a raw string, e.g. of a parameter value: integers, floats and booleans
+    /// are typed accordingly, everything else is kept as string
+    pub fn infer(raw: &str) -> Value {
+        if let Ok(v) = raw.parse::<i64>() {
+            Value::Integer(v)
+        } else if let Some(v) = raw.parse::<f64>().ok().filter(|v| v.is_finite()) {
+            Value::Float(v)
+        } else if let Ok(v) = raw.parse::<bool>() {
+            Value::Bool(v)
+        } else {
+            Value::String(raw.to_string())
+        }
+    }
+
+    /// numeric representation of a value, used to aggregate and plot metrics.
+    /// Durations are represented in seconds and byte sizes in bytes.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(v) => Some(*v as f64),
+            Value::Float(v) => Some(*v),
+            Value::ByteSize(v) => Some(v.as_u64() as f64),
+            Value::Duration(v) => Some(v.as_secs_f64()),
+            _ => None,
+        }
+    }
+
+    /// converts a numeric representation back into a value of the same type as self
+    pub(crate) fn with_f64(&self, v: f64) -> Option<Value> {
+        match self {
+            Value::Integer(_) => Some(Value::Float(v)),
+            Value::Float(_) => Some(Value::Float(v)),
+            Value::ByteSize(_) => Some(Value::ByteSize(ByteSize::b(v.max(0.0).round() as u64))),
+            Value::Duration(_) => Some(Value::Duration(Duration::from_secs_f64(v.max(0.0)))),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregation {
+    Mean,
+    Median,
+    Min,
+    Max,
+}
+
+impl Aggregation {
+    pub const VARIANTS: &'static [&'static str] = &["mean", "median", "min", "max"];
+
+    fn apply(&self, values: &mut [f64]) -> f64 {
+        values.sort_by(f64::total_cmp);
+
+        match self {
+            Aggregation::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            Aggregation::Median => {
+                let mid = values.len() / 2;
+
+                if values.len() == 2 * mid {
+                    (values[mid - 1] + values[mid]) / 2.0
+                } else {
+                    values[mid]
+                }
+            }
+            Aggregation::Min => values[0],
+            Aggregation::Max => values[values.len() - 1],
+        }
+    }
+}
+
+impl FromStr for Aggregation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mean" => Ok(Aggregation::Mean),
+            "median" => Ok(Aggregation::Median),
+            "min" => Ok(Aggregation::Min),
+            "max" => Ok(Aggregation::Max),
+            _ => Err(anyhow!(
+                "unknown aggregation \"{}\", expected one of: {}",
+                s,
+                Aggregation::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Aggregation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Aggregation::Mean => "mean",
+            Aggregation::Median => "median",
+            Aggregation::Min => "min",
+            Aggregation::Max => "max",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeUnit {
+    Ns,
+    Us,
+    Ms,
+    S,
+}
+
+impl TimeUnit {
+    pub const VARIANTS: &'static [&'static str] = &["ns", "us", "ms", "s"];
+
+    fn per_second(&self) -> f64 {
+        match self {
+            TimeUnit::Ns => 1e9,
+            TimeUnit::Us => 1e6,
+            TimeUnit::Ms => 1e3,
+            TimeUnit::S => 1.0,
+        }
+    }
+}
+
+impl FromStr for TimeUnit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ns" => Ok(TimeUnit::Ns),
+            "us" => Ok(TimeUnit::Us),
+            "ms" => Ok(TimeUnit::Ms),
+            "s" => Ok(TimeUnit::S),
+            _ => Err(anyhow!(
+                "unknown time unit \"{}\", expected one of: {}",
+                s,
+                TimeUnit::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TimeUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            TimeUnit::Ns => "ns",
+            TimeUnit::Us => "us",
+            TimeUnit::Ms => "ms",
+            TimeUnit::S => "s",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// how metrics are displayed in tables, unset options keep the plain representation of a value
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayOptions {
+    /// aggregation of repeated values in one cell
+    pub aggregation: Option<Aggregation>,
+    /// fixed unit for durations instead of picking one per value
+    pub unit: Option<TimeUnit>,
+    /// number of decimal places of durations and floats
+    pub precision: Option<usize>,
+}
+
+impl DisplayOptions {
+    /// takes every option from self if it is set and from other otherwise
+    pub fn or(self, other: DisplayOptions) -> DisplayOptions {
+        DisplayOptions {
+            aggregation: self.aggregation.or(other.aggregation),
+            unit: self.unit.or(other.unit),
+            precision: self.precision.or(other.precision),
+        }
+    }
+
+    pub fn format_value(&self, value: &Value) -> String {
+        match (value, self.unit, self.precision) {
+            (Value::Duration(v), Some(unit), Some(precision)) => {
+                format!(
+                    "{:.*}{}",
+                    precision,
+                    v.as_secs_f64() * unit.per_second(),
+                    unit
+                )
+            }
+            (Value::Duration(v), Some(unit), None) => {
+                format!("{}{}", v.as_secs_f64() * unit.per_second(), unit)
+            }
+            (Value::Duration(v), None, Some(precision)) => format!("{:.*?}", precision, v),
+            (Value::Float(v), _, Some(precision)) => format!("{:.*}", precision, v),
+            _ => value.to_string(),
+        }
+    }
+
+    /// formats the aggregated values if an aggregation is set and applicable, all values otherwise
+    pub fn format_values(&self, values: &Values) -> String {
+        if let Some(value) = self.aggregation.and_then(|a| values.aggregate(a)) {
+            return self.format_value(&value);
+        }
+
+        match values.0.as_slice() {
+            [value] => self.format_value(value),
+            values => format!(
+                "{{{}}}",
+                values.iter().map(|v| self.format_value(v)).join(", ")
+            ),
+        }
+    }
+}
+
 pub struct Values(pub Vec<Value>);
 
 impl Values {
     pub fn push(&mut self, v: Value) {
         self.0.push(v);
     }
+
+    /// aggregates all values into one value of the same type.
+    /// Returns None if there are no values or if the values are not numeric and of the same type.
+    pub fn aggregate(&self, aggregation: Aggregation) -> Option<Value> {
+        let first = self.0.first()?;
+
+        if self.0.len() == 1 {
+            return Some(first.clone());
+        }
+
+        let mut numbers = self
+            .0
+            .iter()
+            .map(|v| {
+                if std::mem::discriminant(v) == std::mem::discriminant(first) {
+                    v.as_f64()
+                } else {
+                    None
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        first.with_f64(aggregation.apply(&mut numbers))
+    }
 }
 
 impl fmt::Display for Values {
@@ -244,3 +482,193 @@ impl TryInto<Option<String>> for Value {
         }
     }
 }
+
+impl TryInto<Option<ByteSize>> for Value {
+    type Error = anyhow::Error;
+
+    fn try_into(self) -> Result<Option<ByteSize>, Self::Error> {
+        match self {
+            Value::ByteSize(v) => Ok(Some(v)),
+            _ => Err(anyhow!(
+                "failed to parse {:?} into a Option<ByteSize>",
+                self
+            )),
+        }
+    }
+}
+
+impl TryInto<Option<Duration>> for Value {
+    type Error = anyhow::Error;
+
+    fn try_into(self) -> Result<Option<Duration>, Self::Error> {
+        match self {
+            Value::Duration(v) => Ok(Some(v)),
+            _ => Err(anyhow!(
+                "failed to parse {:?} into a Option<Duration>",
+                self
+            )),
+        }
+    }
+}
+
+impl TryInto<Option<i64>> for Value {
+    type Error = anyhow::Error;
+
+    fn try_into(self) -> Result<Option<i64>, Self::Error> {
+        match self {
+            Value::Integer(v) => Ok(Some(v)),
+            _ => Err(anyhow!("failed to parse {:?} into a Option<i64>", self)),
+        }
+    }
+}
+
+impl TryInto<Option<bool>> for Value {
+    type Error = anyhow::Error;
+
+    fn try_into(self) -> Result<Option<bool>, Self::Error> {
+        match self {
+            Value::Bool(v) => Ok(Some(v)),
+            _ => Err(anyhow!("failed to parse {:?} into a Option<bool>", self)),
+        }
+    }
+}
+
+impl TryInto<Option<DateTime<Utc>>> for Value {
+    type Error = anyhow::Error;
+
+    fn try_into(self) -> Result<Option<DateTime<Utc>>, Self::Error> {
+        match self {
+            Value::Timestamp(v) => Ok(Some(v)),
+            _ => Err(anyhow!(
+                "failed to parse {:?} into a Option<DateTime<Utc>>",
+                self
+            )),
+        }
+    }
+}
+
+impl TryInto<Option<f64>> for Value {
+    type Error = anyhow::Error;
+
+    fn try_into(self) -> Result<Option<f64>, Self::Error> {
+        match self {
+            Value::Float(v) => Ok(Some(v)),
+            _ => Err(anyhow!("failed to parse {:?} into a Option<f64>", self)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn aggregation_keeps_the_type_of_the_values() {
+        let values = Values(vec![
+            Value::Duration(Duration::from_millis(1)),
+            Value::Duration(Duration::from_millis(3)),
+        ]);
+
+        assert_eq!(
+            values.aggregate(Aggregation::Mean),
+            Some(Value::Duration(Duration::from_millis(2)))
+        );
+        assert_eq!(
+            values.aggregate(Aggregation::Max),
+            Some(Value::Duration(Duration::from_millis(3)))
+        );
+    }
+
+    #[test]
+    fn median_of_even_number_of_values_is_the_mean_of_the_middle_values() {
+        let values = Values(vec![
+            Value::Integer(4),
+            Value::Integer(1),
+            Value::Integer(3),
+            Value::Integer(100),
+        ]);
+
+        assert_eq!(
+            values.aggregate(Aggregation::Median),
+            Some(Value::Float(3.5))
+        );
+    }
+
+    #[test]
+    fn aggregating_nan_does_not_panic() {
+        let values = Values(vec![Value::Float(f64::NAN), Value::Float(1.0)]);
+
+        assert_eq!(values.aggregate(Aggregation::Min), Some(Value::Float(1.0)));
+        assert!(values.aggregate(Aggregation::Mean).is_some());
+    }
+
+    #[test]
+    fn values_of_different_types_can_not_be_aggregated() {
+        let values = Values(vec![
+            Value::Integer(4),
+            Value::Duration(Duration::from_millis(3)),
+        ]);
+
+        assert_eq!(values.aggregate(Aggregation::Mean), None);
+
+        let values = Values(vec![
+            Value::String("a".to_string()),
+            Value::String("b".to_string()),
+        ]);
+
+        assert_eq!(values.aggregate(Aggregation::Mean), None);
+    }
+
+    #[test]
+    fn display_options_format_durations_in_a_fixed_unit() {
+        let value = Value::Duration(Duration::from_micros(1500));
+
+        assert_eq!(DisplayOptions::default().format_value(&value), "1.5ms");
+        assert_eq!(
+            DisplayOptions {
+                unit: Some(TimeUnit::Us),
+                precision: Some(0),
+                ..Default::default()
+            }
+            .format_value(&value),
+            "1500us"
+        );
+        assert_eq!(
+            DisplayOptions {
+                precision: Some(2),
+                ..Default::default()
+            }
+            .format_value(&Value::Float(1.0 / 3.0)),
+            "0.33"
+        );
+    }
+
+    #[test]
+    fn display_options_aggregate_values_if_possible() {
+        let options = DisplayOptions {
+            aggregation: Some(Aggregation::Min),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            options.format_values(&Values(vec![Value::Integer(4), Value::Integer(2)])),
+            "2"
+        );
+        assert_eq!(
+            options.format_values(&Values(vec![
+                Value::String("a".to_string()),
+                Value::String("b".to_string())
+            ])),
+            "{a, b}"
+        );
+    }
+
+    #[test]
+    fn types_of_raw_values_are_inferred() {
+        assert_eq!(Value::infer("100"), Value::Integer(100));
+        assert_eq!(Value::infer("-1.5"), Value::Float(-1.5));
+        assert_eq!(Value::infer("true"), Value::Bool(true));
+        assert_eq!(Value::infer("inf"), Value::String("inf".to_string()));
+        assert_eq!(Value::infer("merge"), Value::String("merge".to_string()));
+    }
+}
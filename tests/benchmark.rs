@@ -1,4 +1,30 @@
-use benchie::execute_and_measure;
+#[cfg(target_os = "linux")]
+use benchie::Scheduling;
+#[cfg(unix)]
+use benchie::{benchmark, BenchmarkOptions};
+use benchie::{execute_and_measure, ExecutionOptions};
+#[cfg(unix)]
+use benchie::{
+    load_all_benchmarks, Expectations, Input, OutputStorage, ResourceLimits, Shell, Value,
+};
+#[cfg(unix)]
+use bytesize::ByteSize;
+#[cfg(unix)]
+use common::with_temp_dir;
+#[cfg(unix)]
+use serial_test::serial;
+#[cfg(unix)]
+use std::collections::HashMap;
+#[cfg(unix)]
+use std::fs;
+#[cfg(unix)]
+use std::path::Path;
+#[cfg(unix)]
+use std::time::{Duration, Instant};
+#[cfg(unix)]
+use tempfile::tempdir;
+
+mod common;
 
 #[cfg(unix)]
 const BASIC_COMMAND: &[&str] = &["sleep", "1"];
@@ -8,7 +34,7 @@ const BASIC_COMMAND: &[&str] = &["timeout", "/t", "1"];
 #[test]
 fn execution_and_measurement_basic_functionality() {
     let command: Vec<String> = BASIC_COMMAND.iter().map(|s| s.to_string()).collect();
-    let result = execute_and_measure(&command);
+    let result = execute_and_measure(&command, &ExecutionOptions::default());
 
     assert!(
         result.is_ok(),
@@ -34,10 +60,794 @@ fn execution_and_measurement_basic_functionality() {
 
 #[test]
 fn should_not_panic_if_command_is_invalid() {
-    let result = execute_and_measure(&["adsl;fasdjfoigaids;ifgorajoaidfjoigajoidaa".to_string()]);
+    let result = execute_and_measure(
+        &["adsl;fasdjfoigaids;ifgorajoaidfjoigajoidaa".to_string()],
+        &ExecutionOptions::default(),
+    );
 
     assert!(
         result.is_err(),
         "execution for an invalid command should fail"
     );
 }
+
+#[cfg(unix)]
+#[test]
+fn command_is_executed_in_working_directory_with_environment() {
+    let dir = tempdir().unwrap();
+    let command: Vec<String> = [
+        "sh",
+        "-c",
+        "echo \"@benchie dir=$(pwd -P)\"; echo \"@benchie var=$BENCHIE_TEST\"",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let options = ExecutionOptions {
+        cwd: Some(dir.path().to_path_buf()),
+        env: HashMap::from([("BENCHIE_TEST".to_string(), "42".to_string())]),
+        ..Default::default()
+    };
+
+    let (_, tags, _) = execute_and_measure(&command, &options).expect("execution should succeed");
+
+    assert_eq!(
+        tags.get("dir").map(String::as_str),
+        dir.path().canonicalize().unwrap().to_str()
+    );
+    assert_eq!(tags.get("var").map(String::as_str), Some("42"));
+}
+
+#[test]
+fn should_fail_if_working_directory_does_not_exist() {
+    let command: Vec<String> = BASIC_COMMAND.iter().map(|s| s.to_string()).collect();
+    let options = ExecutionOptions {
+        cwd: Some("does/not/exist".into()),
+        ..Default::default()
+    };
+
+    assert!(execute_and_measure(&command, &options).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn command_and_children_are_killed_after_timeout() {
+    let command: Vec<String> = ["sh", "-c", "sleep 5 & sleep 5"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let options = ExecutionOptions {
+        timeout: Some(Duration::from_millis(200)),
+        ..Default::default()
+    };
+
+    let now = Instant::now();
+    let (result, _, _) = execute_and_measure(&command, &options).expect("execution should succeed");
+
+    assert_eq!(result.timed_out, Some(true));
+    assert_eq!(result.signal_name.as_deref(), Some("SIGKILL"));
+    assert!(
+        now.elapsed() < Duration::from_secs(4),
+        "should not have waited for the command or its children"
+    );
+
+    let (result, _, _) =
+        execute_and_measure(&["true".to_string()], &options).expect("execution should succeed");
+    assert_eq!(result.timed_out, Some(false));
+
+    let (result, _, _) = execute_and_measure(&["true".to_string()], &ExecutionOptions::default())
+        .expect("execution should succeed");
+    assert_eq!(result.timed_out, None, "should only be set with a timeout");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn output_kept_open_by_a_daemon_is_not_awaited_after_timeout() {
+    // the daemon leaves the process group of the command, but inherits its stdout
+    let command: Vec<String> = ["sh", "-c", "setsid sleep 100 & echo $!"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let options = ExecutionOptions {
+        timeout: Some(Duration::from_millis(500)),
+        quiet: true,
+        ..Default::default()
+    };
+
+    let now = Instant::now();
+    let (result, _, output) =
+        execute_and_measure(&command, &options).expect("execution should succeed");
+    let elapsed = now.elapsed();
+
+    let daemon: i32 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .expect("should print its pid");
+    unsafe {
+        libc::kill(daemon, libc::SIGKILL);
+    }
+
+    assert!(
+        elapsed < Duration::from_secs(10),
+        "should not have waited for the daemon, took {elapsed:?}"
+    );
+    assert_eq!(result.timed_out, Some(true));
+    assert!(result.real_time < Duration::from_millis(500));
+}
+
+#[cfg(unix)]
+#[test]
+fn exit_codes_and_signals_are_decoded() {
+    let execute = |script: &str| {
+        let command = vec!["sh".to_string(), "-c".to_string(), script.to_string()];
+        execute_and_measure(&command, &ExecutionOptions::default())
+            .expect("execution should succeed")
+            .0
+    };
+
+    let result = execute("exit 3");
+    assert_eq!(result.exit_code, Some(3));
+    assert_eq!(result.signal, None);
+    assert_eq!(result.core_dumped, None);
+
+    let result = execute("ulimit -c 0; kill -SEGV $$");
+    assert_eq!(result.exit_code, None);
+    assert_eq!(result.signal, Some(libc::SIGSEGV.into()));
+    assert_eq!(result.signal_name.as_deref(), Some("SIGSEGV"));
+    assert_eq!(result.core_dumped, Some(false));
+}
+
+#[cfg(unix)]
+#[test]
+fn resource_limits_are_applied_to_command() {
+    let command: Vec<String> = ["sh", "-c", "echo \"@benchie files=$(ulimit -n)\""]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let options = ExecutionOptions {
+        limits: ResourceLimits {
+            files: Some(42),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let (_, tags, _) = execute_and_measure(&command, &options).expect("execution should succeed");
+
+    assert_eq!(tags.get("files").map(String::as_str), Some("42"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn command_is_pinned_with_lower_priority_and_without_aslr() {
+    let script = [
+        "echo \"@benchie cpus=$(grep Cpus_allowed_list /proc/self/status | cut -f2)\"",
+        "echo \"@benchie nice=$(cut -d ' ' -f 19 /proc/self/stat)\"",
+        "echo \"@benchie persona=$(cat /proc/self/personality)\"",
+    ]
+    .join("; ");
+    let command = vec!["sh".to_string(), "-c".to_string(), script];
+    let options = ExecutionOptions {
+        scheduling: Scheduling {
+            cpus: Some(vec![0]),
+            nice: Some(5),
+            no_aslr: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let (_, tags, _) = execute_and_measure(&command, &options).expect("execution should succeed");
+
+    assert_eq!(tags.get("cpus").map(String::as_str), Some("0"));
+    assert_eq!(tags.get("nice").map(String::as_str), Some("5"));
+    assert_eq!(tags.get("persona").map(String::as_str), Some("00040000"));
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn counters_are_collected_or_skipped_with_warning() {
+    with_temp_dir(|_| {
+        let command: Vec<String> = [
+            "sh",
+            "-c",
+            "i=0; while [ $i -lt 1000 ]; do i=$((i+1)); done",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let options = BenchmarkOptions {
+            execution: ExecutionOptions {
+                counters: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // perf events may be forbidden by perf_event_paranoid or the container
+        benchmark(&command, &HashMap::new(), &options).expect("benchmark should succeed");
+
+        let benchmarks = load_all_benchmarks().unwrap();
+        match benchmarks[0].data.get("task_clock") {
+            Some(Value::Integer(task_clock)) => {
+                assert!(*task_clock > 0, "the loop should take some CPU time");
+                assert!(matches!(
+                    benchmarks[0].data.get("page_faults"),
+                    Some(Value::Integer(_))
+                ));
+            }
+            None => assert!(!benchmarks[0].data.contains_key("instructions")),
+            other => panic!("task_clock should be an integer, got {other:?}"),
+        }
+    })
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn usage_of_process_tree_is_sampled() {
+    let command: Vec<String> = ["sh", "-c", "sleep 0.2; true"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let options = ExecutionOptions {
+        sample_interval: Some(Duration::from_millis(10)),
+        ..Default::default()
+    };
+
+    let (result, _, _) = execute_and_measure(&command, &options).expect("execution should succeed");
+    let timeline = result.timeline;
+
+    assert!(timeline.peak_rss.unwrap() > ByteSize::b(0));
+    assert!(timeline.avg_rss.unwrap() <= timeline.peak_rss.unwrap());
+    assert!(timeline.time_to_peak_rss.unwrap() <= result.real_time);
+    assert!(
+        timeline.timeline.unwrap().split(' ').count() >= 5,
+        "the command should be sampled while it runs"
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn io_of_process_tree_is_accounted() {
+    let dir = tempdir().unwrap();
+    let command: Vec<String> = [
+        "sh",
+        "-c",
+        "head -c 100000 /dev/zero > data; cat data > /dev/null",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let options = ExecutionOptions {
+        cwd: Some(dir.path().to_path_buf()),
+        ..Default::default()
+    };
+
+    let (result, _, _) = execute_and_measure(&command, &options).expect("execution should succeed");
+
+    // head and cat are reaped by the shell, so their I/O is accounted to it
+    assert!(result.io.wchar.unwrap() >= ByteSize::b(200_000));
+    assert!(result.io.rchar.unwrap() >= ByteSize::b(200_000));
+    assert!(result.io.syscw.unwrap() > 0);
+    assert!(result.io.write_bytes.is_some());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+#[serial]
+fn detached_children_are_measured_in_cgroup() {
+    with_temp_dir(|_| {
+        // the detached loop is not reaped by the shell, but keeps stdout open until it is done
+        let command: Vec<String> = [
+            "sh",
+            "-c",
+            "(i=0; while [ $i -lt 20000 ]; do i=$((i+1)); done) & true",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let options = BenchmarkOptions {
+            execution: ExecutionOptions {
+                cgroup: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let own_cgroup = fs::read_to_string("/proc/self/cgroup").unwrap();
+
+        // cgroups may not be delegated to the user running the tests
+        benchmark(&command, &HashMap::new(), &options).expect("benchmark should succeed");
+
+        assert_eq!(
+            fs::read_to_string("/proc/self/cgroup").unwrap(),
+            own_cgroup,
+            "benchie should move back into its cgroup after the runs"
+        );
+
+        let benchmarks = load_all_benchmarks().unwrap();
+        let duration = |key: &str| match benchmarks[0].data.get(key) {
+            Some(Value::Duration(duration)) => Some(*duration),
+            _ => None,
+        };
+
+        if let Some(cgroup_cpu_time) = duration("cgroup_cpu_time") {
+            let rusage_time = duration("user_time").unwrap() + duration("system_time").unwrap();
+            assert!(
+                cgroup_cpu_time > rusage_time,
+                "the detached loop should only be accounted in the cgroup"
+            );
+        }
+    })
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+#[serial]
+fn cgroups_of_all_runs_are_set_up_once() {
+    with_temp_dir(|_| {
+        let options = BenchmarkOptions {
+            runs: 3,
+            // hooks are run by benchie, so they are in its cgroup
+            prepare: Some("grep ^0:: /proc/self/cgroup >> cgroups".to_string()),
+            execution: ExecutionOptions {
+                cgroup: true,
+                quiet: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        benchmark(&["true".to_string()], &HashMap::new(), &options)
+            .expect("benchmark should succeed");
+
+        let benchmarks = load_all_benchmarks().unwrap();
+        // cgroups may not be delegated to the user running the tests
+        if benchmarks[0].data.contains_key("cgroup_cpu_time") {
+            let cgroups = fs::read_to_string("cgroups").unwrap();
+            assert_eq!(cgroups.lines().count(), 3);
+            assert!(
+                cgroups.lines().all(|cgroup| cgroup.ends_with("/self")),
+                "benchie should stay in its leaf cgroup between the runs, got {cgroups}"
+            );
+        }
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn stderr_is_captured_and_scanned_for_tags() {
+    let command: Vec<String> = ["sh", "-c", "echo out; echo \"@benchie from=stderr\" >&2"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let options = ExecutionOptions {
+        capture_stderr: true,
+        quiet: true,
+        ..Default::default()
+    };
+
+    let (_, tags, output) =
+        execute_and_measure(&command, &options).expect("execution should succeed");
+
+    assert_eq!(tags.get("from").map(String::as_str), Some("stderr"));
+    assert_eq!(output.stdout, b"out\n");
+    assert_eq!(
+        output.stderr.as_deref(),
+        Some(&b"@benchie from=stderr\n"[..])
+    );
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn output_is_stored_for_every_run() {
+    with_temp_dir(|dir| {
+        let options = BenchmarkOptions {
+            store_output: Some(OutputStorage::Full),
+            ..Default::default()
+        };
+
+        let command: Vec<String> = ["sh", "-c", "echo hello; echo oops >&2"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        benchmark(&command, &HashMap::new(), &options).expect("benchmark should succeed");
+
+        let benchmarks = load_all_benchmarks().unwrap();
+        let output_id = match benchmarks[0].data.get("output_id") {
+            Some(Value::String(id)) => id.clone(),
+            other => panic!("should have stored an output id, got {other:?}"),
+        };
+        let output_dir = dir.path().join(".benchie/outputs").join(output_id);
+
+        assert_eq!(
+            fs::read_to_string(output_dir.join("stdout")).unwrap(),
+            "hello\n"
+        );
+        assert_eq!(
+            fs::read_to_string(output_dir.join("stderr")).unwrap(),
+            "oops\n",
+            "storing the output should capture stderr as well"
+        );
+        assert_eq!(
+            benchmarks[0].data.get("stdout_hash"),
+            Some(&Value::String(
+                "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03".to_string()
+            ))
+        );
+    })
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn output_is_removed_if_its_benchmark_can_not_be_saved() {
+    with_temp_dir(|dir| {
+        let data_dir = dir.path().join(".benchie");
+        fs::create_dir_all(&data_dir).unwrap();
+        fs::write(data_dir.join("data.json"), "not json").unwrap();
+        let options = BenchmarkOptions {
+            store_output: Some(OutputStorage::Full),
+            ..Default::default()
+        };
+
+        let result = benchmark(
+            &["echo".to_string(), "hello".to_string()],
+            &HashMap::new(),
+            &options,
+        );
+
+        assert!(result.is_err(), "saving the benchmark should fail");
+        let outputs = fs::read_dir(data_dir.join("outputs")).unwrap().count();
+        assert_eq!(outputs, 0, "no output should be left without a benchmark");
+    })
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn runs_not_meeting_expectations_are_marked_invalid() {
+    with_temp_dir(|dir| {
+        let command: Vec<String> = ["sh", "-c", "echo {n}; exit {n}"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut options = BenchmarkOptions {
+            params: vec![("n".to_string(), vec!["0".to_string(), "1".to_string()])],
+            expect: Expectations {
+                exit_code: Some(0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        benchmark(&command, &HashMap::new(), &options).expect("benchmark should succeed");
+
+        let valid: Vec<_> = load_all_benchmarks()
+            .unwrap()
+            .iter()
+            .map(|b| b.data.get("valid").cloned())
+            .collect();
+        assert_eq!(
+            valid,
+            vec![Some(Value::Bool(true)), Some(Value::Bool(false))]
+        );
+
+        fs::remove_dir_all(dir.path().join(".benchie")).unwrap();
+        options.discard_invalid = true;
+
+        benchmark(&command, &HashMap::new(), &options).expect("benchmark should succeed");
+
+        assert_eq!(
+            load_all_benchmarks().unwrap().len(),
+            1,
+            "should not have stored the invalid run"
+        );
+    })
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn input_file_is_fed_to_every_run() {
+    with_temp_dir(|dir| {
+        let input = dir.path().join("corpus.txt");
+        fs::write(&input, "hello\n").unwrap();
+
+        let command: Vec<String> = ["sh", "-c", "echo \"@benchie bytes=$(wc -c | tr -d ' ')\""]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let options = BenchmarkOptions {
+            runs: 2,
+            execution: ExecutionOptions {
+                input: Some(Input::File(input)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        benchmark(&command, &HashMap::new(), &options).expect("benchmark should succeed");
+
+        for benchmark in load_all_benchmarks().unwrap() {
+            assert_eq!(
+                benchmark.data.get("bytes"),
+                Some(&Value::String("6".to_string())),
+                "every run should read the whole input"
+            );
+            assert_eq!(
+                benchmark.data.get("input_size"),
+                Some(&Value::ByteSize(ByteSize::b(6)))
+            );
+        }
+    })
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn environment_is_cleared_and_recorded() {
+    with_temp_dir(|_| {
+        std::env::set_var("BENCHIE_STRAY", "1");
+
+        let command: Vec<String> = [
+            "sh",
+            "-c",
+            "echo \"@benchie stray=${BENCHIE_STRAY:-unset}\"; echo \"@benchie set=$RUST_LOG\"",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let options = BenchmarkOptions {
+            execution: ExecutionOptions {
+                env: HashMap::from([("RUST_LOG".to_string(), "off".to_string())]),
+                env_clear: true,
+                env_keep: vec!["PATH".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        benchmark(&command, &HashMap::new(), &options).expect("benchmark should succeed");
+        std::env::remove_var("BENCHIE_STRAY");
+
+        let benchmarks = load_all_benchmarks().unwrap();
+        let tag = |key: &str| benchmarks[0].data.get(key).cloned();
+
+        assert_eq!(tag("stray"), Some(Value::String("unset".to_string())));
+        assert_eq!(tag("set"), Some(Value::String("off".to_string())));
+        assert_eq!(tag("env.RUST_LOG"), Some(Value::String("off".to_string())));
+        assert!(matches!(tag("env_hash"), Some(Value::String(_))));
+    })
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn machine_is_identified_by_hardware_and_label() {
+    with_temp_dir(|_| {
+        let command = vec!["true".to_string()];
+        let options = BenchmarkOptions {
+            machine: Some("from-config".to_string()),
+            ..Default::default()
+        };
+
+        benchmark(&command, &HashMap::new(), &options).expect("benchmark should succeed");
+        std::env::set_var("BENCHIE_MACHINE", "lab-1");
+        benchmark(&command, &HashMap::new(), &options).expect("benchmark should succeed");
+        std::env::remove_var("BENCHIE_MACHINE");
+
+        let benchmarks = load_all_benchmarks().unwrap();
+        let tag = |i: usize, key: &str| benchmarks[i].data.get(key).cloned();
+
+        assert_eq!(
+            tag(0, "machine_label"),
+            Some(Value::String("from-config".to_string()))
+        );
+        assert_eq!(
+            tag(1, "machine_label"),
+            Some(Value::String("lab-1".to_string()))
+        );
+        assert!(matches!(tag(0, "machine_id"), Some(Value::String(_))));
+        assert_ne!(tag(0, "machine_id"), tag(1, "machine_id"));
+    })
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn binary_and_tool_versions_are_recorded() {
+    with_temp_dir(|dir| {
+        fs::write(dir.path().join("app.sh"), "#!/bin/sh\n").unwrap();
+        let options = BenchmarkOptions {
+            tools: HashMap::from([
+                ("sh".to_string(), "echo 'sh 1.0'".to_string()),
+                ("broken".to_string(), "exit 1".to_string()),
+            ]),
+            ..Default::default()
+        };
+
+        benchmark(
+            &["sh".to_string(), "app.sh".to_string()],
+            &HashMap::new(),
+            &options,
+        )
+        .expect("a broken tool should not fail the benchmark");
+
+        let benchmarks = load_all_benchmarks().unwrap();
+        let tag = |key: &str| benchmarks[0].data.get(key).cloned();
+
+        assert_eq!(tag("tool.sh"), Some(Value::String("sh 1.0".to_string())));
+        assert_eq!(tag("tool.broken"), None);
+        match tag("binary_path") {
+            Some(Value::String(path)) => {
+                assert!(Path::new(&path).is_absolute(), "{path} should be absolute")
+            }
+            other => panic!("should have stored the binary path, got {other:?}"),
+        }
+        assert!(matches!(tag("binary_size"), Some(Value::ByteSize(_))));
+        assert!(matches!(tag("binary_modified"), Some(Value::Timestamp(_))));
+        assert!(matches!(tag("binary_hash"), Some(Value::String(h)) if h.len() == 64));
+    })
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn commands_can_read_from_the_terminal() {
+    with_temp_dir(|_| {
+        let benchie = common::Benchie::new();
+        let read = ["sh", "-c", "read x; echo got=$x"];
+
+        for args in [&read[..], &[&["--timeout", "10s"][..], &read[..]].concat()] {
+            let output = benchie
+                .run_in_terminal(args, "hello\n", Duration::from_secs(20))
+                .expect("benchie should not hang when the command reads from the terminal");
+
+            assert!(
+                output.contains("got=hello"),
+                "the command should have read the input, got {output:?}"
+            );
+        }
+    })
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn commands_run_by_shell_are_single_strings_with_their_program_after_assignments() {
+    with_temp_dir(|_| {
+        let options = BenchmarkOptions {
+            shell: Some(Shell::Sh),
+            ..Default::default()
+        };
+
+        assert!(
+            benchmark(
+                &["echo".to_string(), "a  b".to_string()],
+                &HashMap::new(),
+                &options
+            )
+            .is_err(),
+            "arguments would lose their quoting when joined"
+        );
+
+        benchmark(&["LC_ALL=C ls -a".to_string()], &HashMap::new(), &options)
+            .expect("benchmark should succeed");
+
+        let benchmarks = load_all_benchmarks().unwrap();
+        match benchmarks[0].data.get("binary_path") {
+            Some(Value::String(path)) => assert!(path.ends_with("/ls"), "{path}"),
+            other => panic!("should have summarized ls, got {other:?}"),
+        }
+    })
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn pipelines_are_run_by_shell_with_corrected_times() {
+    with_temp_dir(|_| {
+        let options = BenchmarkOptions {
+            shell: Some(Shell::Sh),
+            correct_shell_overhead: true,
+            ..Default::default()
+        };
+
+        benchmark(
+            &["echo @benchie piped=yes | cat".to_string()],
+            &HashMap::new(),
+            &options,
+        )
+        .expect("benchmark should succeed");
+
+        let benchmarks = load_all_benchmarks().unwrap();
+        let tag = |key: &str| benchmarks[0].data.get(key).cloned();
+
+        assert_eq!(tag("piped"), Some(Value::String("yes".to_string())));
+        assert_eq!(tag("shell"), Some(Value::String("sh".to_string())));
+        match (tag("real_time"), tag("raw_real_time")) {
+            (Some(Value::Duration(corrected)), Some(Value::Duration(raw))) => {
+                assert!(
+                    corrected <= raw,
+                    "corrected time should not exceed raw time"
+                )
+            }
+            other => panic!("should have stored raw and corrected time, got {other:?}"),
+        }
+    })
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn hooks_are_executed_around_runs() {
+    with_temp_dir(|dir| {
+        let log = |entry: &str| format!("echo {entry} >> hooks.log");
+        let command: Vec<String> = ["sh", "-c", "echo run{n} >> hooks.log"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let options = BenchmarkOptions {
+            params: vec![("n".to_string(), vec!["1".to_string(), "2".to_string()])],
+            setup: Some(log("setup")),
+            prepare: Some(log("prepare{n}")),
+            cleanup: Some(log("cleanup")),
+            ..Default::default()
+        };
+
+        benchmark(&command, &HashMap::new(), &options).expect("benchmark should succeed");
+
+        let log = fs::read_to_string(dir.path().join("hooks.log")).unwrap();
+        assert_eq!(
+            log.lines().collect::<Vec<_>>(),
+            vec!["setup", "prepare1", "run1", "cleanup", "prepare2", "run2", "cleanup"]
+        );
+    })
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn failing_hook_aborts_benchmark() {
+    with_temp_dir(|dir| {
+        let options = BenchmarkOptions {
+            prepare: Some("exit 1".to_string()),
+            ..Default::default()
+        };
+
+        let result = benchmark(&["true".to_string()], &HashMap::new(), &options);
+
+        assert!(result.is_err(), "a failing prepare hook should fail");
+        assert!(
+            !dir.path().join(".benchie").exists(),
+            "should not have stored a benchmark"
+        );
+    })
+}
+
+#[cfg(unix)]
+#[test]
+#[serial]
+fn teardown_is_executed_if_a_run_fails() {
+    with_temp_dir(|dir| {
+        let options = BenchmarkOptions {
+            runs: 2,
+            teardown: Some("touch torn-down".to_string()),
+            ..Default::default()
+        };
+
+        let result = benchmark(&["./does-not-exist".to_string()], &HashMap::new(), &options);
+
+        assert!(
+            result.is_err(),
+            "a run which can not be executed should fail"
+        );
+        assert!(
+            dir.path().join("torn-down").exists(),
+            "should have torn down after the failed run"
+        );
+    })
+}
@@ -6,6 +6,8 @@ use std::env::{current_dir, set_current_dir};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::Arc;
+#[cfg(unix)]
+use std::time::Duration;
 use std::{env, fs};
 use tempfile::{tempdir, TempDir};
 
@@ -97,6 +99,75 @@ impl Benchie {
         let stdout = String::from_utf8(stdout).expect("should be utf8");
         unescape(&stdout).expect("should not have unknown escape codes")
     }
+
+    /// runs benchie in a new session with a pseudo terminal as controlling terminal, types the
+    /// input into it and returns everything printed to it, or None if benchie did not finish
+    #[cfg(unix)]
+    pub fn run_in_terminal(&self, args: &[&str], input: &str, timeout: Duration) -> Option<String> {
+        use std::fs::File;
+        use std::io::{Read, Write};
+        use std::os::unix::io::FromRawFd;
+        use std::os::unix::process::CommandExt;
+        use std::process::Stdio;
+        use std::thread;
+        use std::time::Instant;
+
+        let (mut master, slave) = unsafe {
+            let (mut master, mut slave) = (0, 0);
+            let result = libc::openpty(
+                &mut master,
+                &mut slave,
+                std::ptr::null_mut(),
+                std::ptr::null(),
+                std::ptr::null(),
+            );
+            assert_eq!(result, 0, "should be able to open a pseudo terminal");
+            (File::from_raw_fd(master), slave)
+        };
+        let terminal = || unsafe { Stdio::from_raw_fd(libc::dup(slave)) };
+
+        let mut command = Command::new(&self.executable);
+        command
+            .args(args)
+            .stdin(terminal())
+            .stdout(terminal())
+            .stderr(terminal());
+        unsafe {
+            command.pre_exec(|| {
+                libc::setsid();
+                libc::ioctl(0, libc::TIOCSCTTY, 0);
+                Ok(())
+            });
+        }
+
+        let mut child = command.spawn().expect("should be able to run benchie");
+        // the terminal ends once benchie and its commands closed it, so no copy may be kept
+        drop(command);
+        unsafe { libc::close(slave) };
+
+        let mut output = master.try_clone().unwrap();
+        let reader = thread::spawn(move || {
+            let mut printed = vec![];
+            // fails with EIO once all processes closed the terminal
+            let _ = output.read_to_end(&mut printed);
+            String::from_utf8_lossy(&printed).to_string()
+        });
+        master.write_all(input.as_bytes()).unwrap();
+
+        let started = Instant::now();
+        while child.try_wait().unwrap().is_none() {
+            if started.elapsed() > timeout {
+                // benchie is the leader of the session and its first process group
+                unsafe { libc::kill(-(child.id() as i32), libc::SIGKILL) };
+                let _ = child.wait();
+                return None;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        drop(master);
+        Some(reader.join().unwrap())
+    }
 }
 
 pub fn build_git_repo<P: AsRef<Path>>(path: P) {
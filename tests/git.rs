@@ -1,7 +1,7 @@
 mod common;
 
 use crate::common::{build_git_repo, commit};
-use benchie::{read_git_info, GitError};
+use benchie::{read_first_parent_history, read_git_info, GitError};
 use common::with_temp_dir;
 use git2::Repository;
 use serial_test::serial;
@@ -119,3 +119,23 @@ fn git_info_if_repo_is_not_at_head_commit() {
         );
     });
 }
+
+#[test]
+#[serial]
+fn first_parent_history_is_ordered_from_newest_to_oldest() {
+    with_temp_dir(|dir| {
+        build_git_repo(dir.path());
+        let _ = fs::write("./README.md", "# Header and new content");
+
+        commit(&["README.md"], "update");
+
+        let history = read_first_parent_history(10).expect("should read history");
+
+        let messages: Vec<_> = history.iter().map(|(_, msg)| msg.as_str()).collect();
+        assert_eq!(messages, vec!["update", "initial commit"]);
+
+        let history = read_first_parent_history(1).expect("should read history");
+
+        assert_eq!(history.len(), 1, "history should be limited");
+    });
+}
@@ -1,6 +1,7 @@
 use crate::common::with_temp_data_dir;
 use common::Benchie;
 use lazy_static::lazy_static;
+use std::fs;
 
 mod common;
 
@@ -11,7 +12,7 @@ lazy_static! {
 #[test]
 fn show_command_is_deterministic_and_sorted() {
     with_temp_data_dir(|dir| {
-        (0..10).into_iter().for_each(|_| {
+        (0..10).for_each(|_| {
             let output = BENCHIE.run_in_dir(&["show"], dir.path());
 
             assert_eq!(output, SHOW_OUTPUT);
@@ -19,6 +20,41 @@ fn show_command_is_deterministic_and_sorted() {
     })
 }
 
+#[test]
+fn show_command_ignores_malformed_config() {
+    with_temp_data_dir(|dir| {
+        fs::write(dir.path().join("benchie.toml"), "[display\n").unwrap();
+
+        let output = BENCHIE.run_in_dir(&["show"], dir.path());
+
+        assert!(
+            output.contains("warning: ignoring the config"),
+            "{}",
+            output
+        );
+        assert!(output.contains("all your 9 saved benchmarks"), "{}", output);
+    })
+}
+
+#[test]
+fn show_chart_uses_display_options_of_config() {
+    with_temp_data_dir(|dir| {
+        fs::write(
+            dir.path().join("benchie.toml"),
+            "[display]\nunit = \"us\"\nprecision = 0\n",
+        )
+        .unwrap();
+
+        let output = BENCHIE.run_in_dir(
+            &["show", "--row", "algorithm", "--chart", "bar", "real_time"],
+            dir.path(),
+        );
+
+        assert!(output.contains("us"), "{}", output);
+        assert!(!output.contains("ms"), "{}", output);
+    })
+}
+
 const SHOW_OUTPUT: &str = "Basic information about all your 9 saved benchmarks:
 +-----------------+-------------+----------------------------------------------------------------------------------------------------+
 | key             | occurrences | example values                                                                                     |
@@ -139,3 +175,23 @@ row: algorithm, col: elements, metric: real_time
 +------------+--------------------------+--------------------------+------------+
 
 ";
+
+#[test]
+fn show_bar_chart_works() {
+    with_temp_data_dir(|dir| {
+        let output = BENCHIE.run_in_dir(
+            &["show", "--chart", "bar", "--row", "algorithm", "real_time"],
+            dir.path(),
+        );
+
+        assert_eq!(output, SHOW_BAR_CHART_OUTPUT);
+    })
+}
+
+const SHOW_BAR_CHART_OUTPUT: &str = "Showing bar chart with:
+row: algorithm, metric: real_time
+
+bubblesort │█████████████████████████████████████████████████████████ 2.189ms
+mergesort  │█████████████████████████████████████████████▊            1.755683ms
+
+";
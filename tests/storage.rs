@@ -1,6 +1,9 @@
 mod common;
 
-use benchie::{append_benchmark, load_all_benchmarks, Benchmark, ExecutionResult, GitInfo, Value};
+use benchie::{
+    append_benchmark, load_all_benchmarks, Benchmark, Config, ExecutionResult, GitInfo, Storage,
+    System, Value,
+};
 use common::{build_git_repo, with_temp_dir};
 use serial_test::serial;
 use std::collections::HashMap;
@@ -113,6 +116,25 @@ fn append_data_with_existing_dir_and_data() {
     })
 }
 
+#[test]
+#[serial]
+fn many_benchmarks_can_be_appended() {
+    with_temp_dir(|_| {
+        for _ in 0..3 {
+            append_benchmark(&create_benchmark()).expect("should succeed to append a benchmark");
+        }
+
+        let raw = fs::read_to_string(".benchie/data.json").unwrap();
+
+        assert_eq!(load_all_benchmarks().unwrap().len(), 3);
+        assert_eq!(
+            raw.matches("\"user_time\"").count(),
+            3,
+            "fields should not be duplicated when appending"
+        );
+    })
+}
+
 #[test]
 #[serial]
 fn should_save_tags_in_benchmark() {
@@ -121,7 +143,9 @@ fn should_save_tags_in_benchmark() {
 
         let benchmarks = load_all_benchmarks().expect("should successfully load benchmarks");
 
-        let benchmark = benchmarks.get(0).expect("should have loaded one benchmark");
+        let benchmark = benchmarks
+            .first()
+            .expect("should have loaded one benchmark");
 
         assert_eq!(
             benchmark.data.get("key"),
@@ -169,6 +193,67 @@ fn should_use_current_dir_as_base_path_if_repo_can_not_be_found() {
     })
 }
 
+#[test]
+#[serial]
+fn should_use_storage_location_and_format_of_config_file() {
+    with_temp_dir(|dir| {
+        build_git_repo(dir.path());
+
+        fs::write(
+            dir.path().join("benchie.toml"),
+            "[storage]\npath = \"results/benchie\"\nformat = \"pretty-json\"\n",
+        )
+        .expect("should be able to write config file");
+
+        let subpath = dir.path().join("something");
+        let _ = create_dir(&subpath);
+        let _ = set_current_dir(&subpath);
+
+        append_benchmark(&create_benchmark()).expect("should succeed to append a benchmark");
+
+        let data_file_path = dir.path().join("results/benchie/data.json");
+        let raw = fs::read_to_string(&data_file_path)
+            .expect("should have created a data file at the configured location");
+
+        assert!(
+            raw.lines().count() > 1,
+            "should have pretty printed the data"
+        );
+        assert_eq!(load_all_benchmarks().unwrap().len(), 1);
+    })
+}
+
+#[test]
+#[serial]
+fn storage_is_resolved_once() {
+    with_temp_dir(|dir| {
+        fs::write(
+            dir.path().join("benchie.toml"),
+            "[storage]\npath = \"first\"\n",
+        )
+        .expect("should be able to write config file");
+
+        let storage = Storage::new(&Config::load().unwrap().storage);
+
+        fs::write(
+            dir.path().join("benchie.toml"),
+            "[storage]\npath = \"second\"\n",
+        )
+        .expect("should be able to write config file");
+
+        storage
+            .append_benchmark(&create_benchmark())
+            .expect("should succeed to append a benchmark");
+
+        assert!(dir.path().join("first/data.json").exists());
+        assert!(
+            !dir.path().join("second").exists(),
+            "a changed config should not move the data file of a resolved storage"
+        );
+        assert_eq!(storage.load_all_benchmarks().unwrap().len(), 1);
+    })
+}
+
 fn create_execution_result() -> ExecutionResult {
     ExecutionResult {
         real_time: Duration::from_secs(1),
@@ -194,6 +279,7 @@ fn create_benchmark() -> Benchmark {
         &["ls".to_string(), "-la".to_string()],
         &result,
         &Some(info),
+        System::default(),
         &tags,
     )
 }
@@ -0,0 +1,80 @@
+#![cfg(unix)]
+
+use benchie::{load_all_benchmarks, Value};
+use common::{build_git_repo, with_temp_dir, Benchie};
+use serial_test::serial;
+use std::fs;
+
+mod common;
+
+#[test]
+#[serial]
+fn failing_suite_entries_are_torn_down() {
+    with_temp_dir(|dir| {
+        build_git_repo(dir.path());
+        fs::write(
+            dir.path().join("benchie-suites.toml"),
+            r#"
+[broken.missing]
+command = ["./does-not-exist"]
+setup = "touch set-up"
+teardown = "touch torn-down"
+"#,
+        )
+        .unwrap();
+
+        Benchie::new().run(&["run", "broken"]);
+
+        assert!(dir.path().join("set-up").exists());
+        assert!(
+            dir.path().join("torn-down").exists(),
+            "should have torn down the failed entry"
+        );
+    })
+}
+
+#[test]
+#[serial]
+fn suite_entries_are_stored_as_tagged_benchmarks() {
+    with_temp_dir(|dir| {
+        build_git_repo(dir.path());
+        fs::create_dir(dir.path().join("work")).unwrap();
+        fs::write(
+            dir.path().join("benchie-suites.toml"),
+            r#"
+[startup.shell]
+command = ["sh", "-c", "echo @benchie size=$SIZE"]
+cwd = "work"
+env = { SIZE = "100" }
+setup = "touch setup-done"
+runs = 2
+
+[other.true]
+command = ["true"]
+"#,
+        )
+        .unwrap();
+
+        Benchie::new().run(&["run", "startup"]);
+
+        assert!(
+            dir.path().join("work/setup-done").exists(),
+            "should have run the setup in the working directory"
+        );
+
+        let benchmarks = load_all_benchmarks().unwrap();
+        assert_eq!(
+            benchmarks.len(),
+            2,
+            "should have stored one benchmark per run"
+        );
+
+        for benchmark in benchmarks {
+            let tag = |key: &str| benchmark.data.get(key).cloned();
+
+            assert_eq!(tag("suite"), Some(Value::String("startup".to_string())));
+            assert_eq!(tag("name"), Some(Value::String("shell".to_string())));
+            assert_eq!(tag("size"), Some(Value::String("100".to_string())));
+        }
+    })
+}